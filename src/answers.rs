@@ -0,0 +1,180 @@
+//! Answers we got (and had accepted) for our real inputs, so refactors can be checked against them.
+//!
+//! They're kept in `answers.txt` next to `input/`, one per line as `day part answer`:
+//!
+//! ```text
+//! # day part answer
+//! 1 1 54916
+//! 1 2 54728
+//! ```
+//!
+//! Blank lines and lines starting with `#` are ignored. Answers are compared by how they display.
+
+use std::{collections::BTreeMap, fmt, io, path::Path, str::FromStr};
+
+use crate::{read_input, Answer, Context, Input, SolveError, DAYS};
+
+/// Recorded answers, keyed by day and part
+#[derive(Debug, Default)]
+pub struct Answers(BTreeMap<(u8, u8), String>);
+
+impl Answers {
+    /// Reads an answers file, which has to exist
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        std::fs::read_to_string(path)?
+            .parse()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.0.get(&(day, part)).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A line of the answers file that isn't `day part answer`. This is about our own file, not a puzzle input,
+/// so it's kept apart from `SolveError`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnswersError {
+    /// 1-based line of the file
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// What should have been there instead
+    pub expected: String,
+}
+
+impl AnswersError {
+    /// Points at `span`, which has to be a slice of `line`, the `number`th line
+    fn at(number: usize, line: &str, span: &str, expected: impl Into<String>) -> Self {
+        let offset = span.as_ptr() as usize - line.as_ptr() as usize;
+        Self { line: number, column: line[..offset].chars().count() + 1, expected: expected.into() }
+    }
+}
+
+impl fmt::Display for AnswersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bad answer at line {}, column {}: expected {}", self.line, self.column, self.expected)
+    }
+}
+
+impl std::error::Error for AnswersError {}
+
+impl FromStr for Answers {
+    type Err = AnswersError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut answers = BTreeMap::new();
+        for (number, line) in (1..).zip(s.lines()) {
+            let error = |span, expected: &str| AnswersError::at(number, line, span, expected);
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let mut fields = trimmed.splitn(3, char::is_whitespace);
+            let (Some(day), Some(part), Some(answer)) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(error(line, "`day part answer`"));
+            };
+            let day: u8 = day.parse().map_err(|_| error(day, "a day number"))?;
+            if !(1..=25).contains(&day) {
+                return Err(error(line, "a day from 1 to 25"));
+            }
+            let part = match part {
+                "1" => 1,
+                "2" => 2,
+                _ => return Err(error(part, "part `1` or `2`")),
+            };
+            if answers.insert((day, part), answer.trim().to_string()).is_some() {
+                return Err(error(line, &format!("one answer for day {day} part {part}")));
+            }
+        }
+        Ok(Self(answers))
+    }
+}
+
+/// A recorded answer checked against what the solver now says
+#[derive(Debug)]
+pub struct Check {
+    pub day: u8,
+    pub part: u8,
+    pub expected: String,
+    pub result: Result<Answer, SolveError>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.result.as_ref().is_ok_and(|answer| answer.to_string() == self.expected)
+    }
+}
+
+/// Solves every part that has both an input in `input_dir` and a recorded answer, in order.
+/// Days without an input are skipped; other read errors are returned.
+pub fn verify(input_dir: impl AsRef<Path>, answers: &Answers) -> io::Result<Vec<Check>> {
+    let mut checks = Vec::new();
+    for entry in DAYS {
+        let parts: Vec<(u8, &str)> = [1, 2]
+            .into_iter()
+            .filter_map(|part| Some((part, answers.get(entry.number, part)?)))
+            .collect();
+        if parts.is_empty() {
+            continue;
+        }
+        let input = match read_input(&input_dir, entry.number) {
+            Ok(input) => Input::from(input),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error),
+        };
+        let parsed = (entry.parse)(&input);
+        for (part, expected) in parts {
+            let solver = entry.solver(part).expect("answers only have parts 1 and 2");
+            checks.push(Check {
+                day: entry.number,
+                part,
+                expected: expected.to_string(),
+                result: parsed.clone().and_then(|parsed| solver(&parsed, &Context::new())),
+            });
+        }
+    }
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let answers: Answers = "# day part answer\n1 1 54916\n\n12 2 some text\n".parse().unwrap();
+        assert_eq!(answers.get(1, 1), Some("54916"));
+        assert_eq!(answers.get(12, 2), Some("some text"));
+        assert_eq!(answers.get(1, 2), None);
+
+        let err = "1 1 5\n1 3 7\n".parse::<Answers>().unwrap_err();
+        assert_eq!(err, AnswersError { line: 2, column: 3, expected: "part `1` or `2`".into() });
+        assert!("1 1 5\n1 1 6\n".parse::<Answers>().is_err());
+    }
+
+    /// Checks the real inputs in `input/` against `answers.txt`, when they're around
+    #[test]
+    fn test_recorded_answers() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let answers = match Answers::read(root.join("answers.txt")) {
+            Ok(answers) => answers,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                eprintln!("skipping test_recorded_answers: there's no answers.txt to check against");
+                return;
+            }
+            Err(error) => panic!("could not read answers.txt: {error}"),
+        };
+        let checks = verify(root.join("input"), &answers).unwrap();
+        let failed: Vec<String> = checks
+            .iter()
+            .filter(|check| !check.passed())
+            .map(|check| format!("day {} part {}: expected {}, got {:?}", check.day, check.part, check.expected, check.result))
+            .collect();
+        assert!(failed.is_empty(), "answers changed:\n{}", failed.join("\n"));
+    }
+}
@@ -0,0 +1,258 @@
+//! JSON API for tooling, mounted under `/api/v1`
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Path, Request, State},
+    http::{header::CONTENT_TYPE, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::Instrument;
+
+use aoc2023::{Answer, Input, PartInput, SolveError, DAYS};
+
+use crate::{
+    cache::{self, Cached},
+    trace, AppState,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/days", get(list_days))
+        .route("/days/:day/parts/:part", post(solve))
+}
+
+#[derive(Serialize)]
+struct DaySummary {
+    day: u8,
+    title: &'static str,
+    parts: &'static [u8],
+}
+
+async fn list_days() -> Json<Vec<DaySummary>> {
+    Json(
+        DAYS.iter()
+            .map(|entry| DaySummary {
+                day: entry.number,
+                title: entry.title,
+                parts: entry.parts,
+            })
+            .collect(),
+    )
+}
+
+#[derive(Serialize)]
+struct Solution {
+    day: u8,
+    part: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    answer: Option<Answer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ApiError>,
+    /// Parsing and solving together
+    elapsed_ms: f64,
+    parse_ms: f64,
+    /// Missing when parsing failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solve_ms: Option<f64>,
+    /// Whether the answer was remembered from an earlier request, in which case the times are from that one
+    cached: bool,
+    /// Where the answer can be shared from, like `/r/3fZq9LkA`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permalink: Option<String>,
+}
+
+async fn solve(
+    State(state): State<AppState>,
+    ApiPath((day, part)): ApiPath<(u8, u8)>,
+    ApiInput(input): ApiInput,
+) -> Result<Response, ApiError> {
+    let entry = aoc2023::day(day)
+        .ok_or_else(|| ApiError::not_found(format!("day {day} does not exist")))?;
+    let solver = entry
+        .solver(part)
+        .ok_or_else(|| ApiError::not_found(format!("part {part} does not exist")))?;
+
+    let input = Input::from(input);
+    let key = state.cache.key(day, part, &input);
+    if let Some(Cached { answer, parse, solve, permalink }) = state.cache.get(&key) {
+        let (parse_ms, solve_ms) = (ms(parse), ms(solve));
+        let solution = Solution {
+            day,
+            part,
+            answer: Some(answer),
+            error: None,
+            elapsed_ms: parse_ms + solve_ms,
+            parse_ms,
+            solve_ms: Some(solve_ms),
+            cached: true,
+            permalink: permalink.map(|id| format!("/r/{id}")),
+        };
+        return Ok((cache::header(true), Json(solution)).into_response());
+    }
+
+    let span = trace::Solve::new(&state.metrics, day, part, &input);
+    let start = Instant::now();
+    let parsed = state.pool.parse(entry.parse, input).await;
+    let parse = start.elapsed();
+    let (result, solve) = match parsed {
+        Ok(parsed) => {
+            let start = Instant::now();
+            let result = state.pool.solve(solver, parsed).instrument(span.span.clone()).await;
+            (result, Some(start.elapsed()))
+        }
+        Err(error) => (Err(error), None),
+    };
+    span.finish(solve, &result);
+    let mut permalink = None;
+    if let (Ok(answer), Some(solve)) = (&result, solve) {
+        permalink = Some(state.results.record(day, part, span.input, answer, parse, solve));
+        state.cache.insert(key, Cached { answer: answer.clone(), parse, solve, permalink: permalink.clone() });
+    }
+    let (parse_ms, solve_ms) = (ms(parse), solve.map(ms));
+    let elapsed_ms = parse_ms + solve_ms.unwrap_or(0.0);
+
+    let (status, answer, error) = match result {
+        Ok(answer) => (StatusCode::OK, Some(answer), None),
+        Err(error) => {
+            let error = ApiError::from(error);
+            (error.status, None, Some(error))
+        }
+    };
+    let permalink = permalink.map(|id| format!("/r/{id}"));
+    let solution = Solution { day, part, answer, error, elapsed_ms, parse_ms, solve_ms, cached: false, permalink };
+    Ok((status, cache::header(false), Json(solution)).into_response())
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Like `Path`, but with a bad path answered in the API's error shape rather than as plain text
+struct ApiPath<T>(T);
+
+#[async_trait]
+impl<S: Send + Sync, T: DeserializeOwned + Send> FromRequestParts<S> for ApiPath<T> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Path::from_request_parts(parts, state)
+            .await
+            .map(|Path(value)| Self(value))
+            .map_err(|rejection| ApiError::bad_request(rejection.body_text()))
+    }
+}
+
+/// The puzzle input, sent either as `{"input": "..."}` JSON or as the raw request body
+struct ApiInput(String);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S> for ApiInput {
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json = req.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/json"));
+
+        if is_json {
+            let Json(PartInput { input }) = Json::from_request(req, state)
+                .await
+                .map_err(|rejection| ApiError::bad_request(rejection.body_text()))?;
+            Ok(Self(input))
+        } else {
+            String::from_request(req, state)
+                .await
+                .map(Self)
+                .map_err(|rejection| ApiError::bad_request(rejection.body_text()))
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    #[serde(skip)]
+    status: StatusCode,
+    kind: &'static str,
+    message: String,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    location: Option<Location>,
+}
+
+#[derive(Serialize)]
+struct Location {
+    line: usize,
+    column: usize,
+    expected: String,
+}
+
+impl ApiError {
+    fn not_found(message: String) -> Self {
+        Self { status: StatusCode::NOT_FOUND, kind: "not_found", message, location: None }
+    }
+
+    fn bad_request(message: String) -> Self {
+        Self { status: StatusCode::BAD_REQUEST, kind: "bad_request", message, location: None }
+    }
+}
+
+impl From<SolveError> for ApiError {
+    fn from(error: SolveError) -> Self {
+        let (message, kind) = (error.to_string(), error.kind());
+        match error {
+            SolveError::Parse { line, column, expected } => Self {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                kind,
+                message,
+                location: Some(Location { line, column, expected }),
+            },
+            SolveError::Unimplemented { .. } => Self {
+                status: StatusCode::NOT_IMPLEMENTED,
+                kind,
+                message,
+                location: None,
+            },
+            SolveError::Timeout { .. } => Self {
+                status: StatusCode::GATEWAY_TIMEOUT,
+                kind,
+                message,
+                location: None,
+            },
+            SolveError::Cancelled => Self {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                kind,
+                message,
+                location: None,
+            },
+            SolveError::Overflow => Self {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                kind,
+                message,
+                location: None,
+            },
+            SolveError::Panicked(_) => Self {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                kind,
+                message,
+                location: None,
+            },
+        }
+    }
+}
+
+/// Wraps errors that stop a request before solving, so they have the same shape as a failed `Solution`
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: ApiError,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ErrorResponse { error: self })).into_response()
+    }
+}
@@ -0,0 +1,92 @@
+//! The files under `/static`, built into the binary so the server doesn't depend on its working directory
+
+use axum::{
+    extract::Path,
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+
+struct Asset {
+    /// Under `/static`
+    path: &'static str,
+    body: &'static [u8],
+    etag: u64,
+}
+
+macro_rules! asset {
+    ($path:literal) => {{
+        const BODY: &[u8] = include_bytes!(concat!("../static/", $path));
+        Asset { path: $path, body: BODY, etag: fnv1a(BODY) }
+    }};
+}
+
+const ASSETS: &[Asset] = &[asset!("css/tailwind.css")];
+
+/// A hash that can be worked out at compile time, which is all an ETag needs
+const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Serves a built-in file. The URLs don't change when the files do, so browsers are told to check back
+/// every time, which costs them a `304 Not Modified` while the ETag still matches.
+pub async fn serve(Path(path): Path<String>, headers: HeaderMap) -> Response {
+    let Some(asset) = ASSETS.iter().find(|asset| asset.path == path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let etag = format!("\"{:016x}\"", asset.etag);
+    let cache = [(ETAG, etag.clone()), (CACHE_CONTROL, "public, no-cache".to_owned())];
+
+    let matches = headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || tag.trim() == etag);
+    if matches {
+        return (StatusCode::NOT_MODIFIED, cache).into_response();
+    }
+    (cache, [(CONTENT_TYPE, content_type(asset.path))], asset.body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_etag() {
+        let response = serve(Path("css/tailwind.css".into()), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/css; charset=utf-8");
+        let etag = response.headers()[ETAG].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, etag);
+        let response = serve(Path("css/tailwind.css".into()), headers).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = serve(Path("css/missing.css".into()), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,203 @@
+//! `aoc2023 bench`: timing every part over several runs and comparing against a saved baseline
+
+use std::{
+    fs,
+    path::PathBuf,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use aoc2023::Context;
+
+use crate::{inputs, ms, parts};
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Only time this day
+    day: Option<u8>,
+    /// Only time this part
+    #[arg(long, short, value_parser = clap::value_parser!(u8).range(1..=2))]
+    part: Option<u8>,
+    /// How many times to solve each part
+    #[arg(long, short, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    runs: u32,
+    /// Where the `dayNN.txt` inputs are kept
+    #[arg(long, default_value = "input")]
+    input_dir: PathBuf,
+    /// Save the timings to this file, to compare later runs against
+    #[arg(long)]
+    save: Option<PathBuf>,
+    /// Compare against timings saved earlier with `--save`
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// How much slower (in percent) the median can get before it counts as a regression
+    #[arg(long, default_value_t = 10.0)]
+    threshold: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Baseline {
+    runs: u32,
+    timings: Vec<Timing>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Timing {
+    day: u8,
+    /// `None` for parsing the input, which the parts share
+    part: Option<u8>,
+    min_ms: f64,
+    median_ms: f64,
+    max_ms: f64,
+}
+
+impl Timing {
+    fn new(day: u8, part: Option<u8>, mut times: Vec<Duration>) -> Self {
+        times.sort();
+        let mid = times.len() / 2;
+        let median = if times.len().is_multiple_of(2) {
+            (times[mid - 1] + times[mid]) / 2
+        } else {
+            times[mid]
+        };
+        Self {
+            day,
+            part,
+            min_ms: ms(times[0]),
+            median_ms: ms(median),
+            max_ms: ms(times[times.len() - 1]),
+        }
+    }
+}
+
+pub fn bench(args: BenchArgs) -> ExitCode {
+    let baseline: Option<Baseline> = match &args.baseline {
+        Some(path) => match fs::read_to_string(path).map(|text| serde_json::from_str(&text)) {
+            Ok(Ok(baseline)) => Some(baseline),
+            Ok(Err(error)) => {
+                eprintln!("{} is not a baseline: {error}", path.display());
+                return ExitCode::FAILURE;
+            }
+            Err(error) => {
+                eprintln!("could not read {}: {error}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    let Some(inputs) = inputs(&args.input_dir, args.day) else {
+        return ExitCode::FAILURE;
+    };
+
+    let mut failed = false;
+    let mut timings = Vec::new();
+    'days: for (entry, input) in inputs {
+        let mut times = Vec::with_capacity(args.runs as usize);
+        let mut parsed = None;
+        for _ in 0..args.runs {
+            let start = Instant::now();
+            let result = (entry.parse)(&input);
+            times.push(start.elapsed());
+            match result {
+                Ok(result) => parsed = Some(result),
+                Err(error) => {
+                    eprintln!("day {} failed to parse: {error}", entry.number);
+                    failed = true;
+                    continue 'days;
+                }
+            }
+        }
+        timings.push(Timing::new(entry.number, None, times));
+        let parsed = parsed.expect("there's at least one run");
+
+        'parts: for part in parts(entry, args.part) {
+            let solver = entry.solver(part).expect("clap only accepts parts 1 and 2");
+            let mut times = Vec::with_capacity(args.runs as usize);
+            for _ in 0..args.runs {
+                let start = Instant::now();
+                let result = solver(&parsed, &Context::new());
+                times.push(start.elapsed());
+                if let Err(error) = result {
+                    eprintln!("day {} part {part} failed: {error}", entry.number);
+                    failed = true;
+                    continue 'parts;
+                }
+            }
+            timings.push(Timing::new(entry.number, Some(part), times));
+        }
+    }
+
+    let regressions = print_table(&timings, baseline.as_ref(), args.threshold);
+
+    if let Some(path) = &args.save {
+        let baseline = Baseline { runs: args.runs, timings };
+        if let Err(error) = fs::write(path, serde_json::to_string_pretty(&baseline).unwrap()) {
+            eprintln!("could not save the baseline to {}: {error}", path.display());
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if regressions > 0 {
+        eprintln!("{regressions} part(s) got more than {}% slower", args.threshold);
+    }
+    if failed || regressions > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Prints the timings and how their medians changed from the baseline, returning the number of regressions
+fn print_table(timings: &[Timing], baseline: Option<&Baseline>, threshold: f64) -> usize {
+    let mut regressions = 0;
+    print!("Day   Part  {:>12}  {:>12}  {:>12}", "Min", "Median", "Max");
+    if baseline.is_some() {
+        print!("  {:>12}  {:>8}", "Baseline", "Change");
+    }
+    println!();
+
+    for timing in timings {
+        let part = timing.part.map_or("parse".to_string(), |part| part.to_string());
+        print!(
+            "{:>3}  {:>5}  {:>9.3} ms  {:>9.3} ms  {:>9.3} ms",
+            timing.day, part, timing.min_ms, timing.median_ms, timing.max_ms
+        );
+        let old = baseline.and_then(|baseline| {
+            baseline.timings.iter().find(|old| old.day == timing.day && old.part == timing.part)
+        });
+        match (baseline, old) {
+            // A zero or broken baseline median can't be compared against, so it never counts as a regression
+            (Some(_), Some(old)) if !(old.median_ms > 0.0 && old.median_ms.is_finite()) => {
+                print!("  {:>9.3} ms  {:>8}", old.median_ms, "n/a");
+            }
+            (Some(_), Some(old)) => {
+                let change = (timing.median_ms - old.median_ms) / old.median_ms * 100.0;
+                print!("  {:>9.3} ms  {:>+7.1}%", old.median_ms, change);
+                if change > threshold {
+                    regressions += 1;
+                    print!("  REGRESSED");
+                }
+            }
+            (Some(_), None) => print!("  {:>12}  {:>8}", "-", "new"),
+            (None, _) => {}
+        }
+        println!();
+    }
+    regressions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing() {
+        let times = [4, 1, 3, 2].map(Duration::from_millis).to_vec();
+        let timing = Timing::new(1, Some(1), times);
+        assert_eq!((timing.min_ms, timing.median_ms, timing.max_ms), (1.0, 2.5, 4.0));
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+
+use aoc2023::{Answer, Context, DayEntry, Input, DAYS};
+
+mod bench;
+mod verify;
+
+/// Runs the AoC 2023 solutions from the command line
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solves a day, or every day with `--all`
+    Run(RunArgs),
+    /// Times the solutions on the inputs in the input directory (build with `--release`)
+    Bench(bench::BenchArgs),
+    /// Checks the solutions against the answers recorded for our inputs
+    Verify(verify::VerifyArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// The day to solve
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    day: Option<u8>,
+    /// Only solve this part
+    #[arg(long, short, value_parser = clap::value_parser!(u8).range(1..=2))]
+    part: Option<u8>,
+    /// Read the input from this file, or from stdin with `-`, instead of the input directory
+    #[arg(long, short, conflicts_with = "all")]
+    input: Option<PathBuf>,
+    /// Solve every day that has an input in the input directory
+    #[arg(long)]
+    all: bool,
+    /// Where the `dayNN.txt` inputs are kept
+    #[arg(long, default_value = "input")]
+    input_dir: PathBuf,
+    /// How to print the answers and timings
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+/// One solved (or failed) part, as printed
+#[derive(Serialize)]
+struct Run {
+    day: u8,
+    part: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    answer: Option<Answer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Parsing and solving together
+    elapsed_ms: f64,
+    /// Parsing is shared by a day's parts, so each of them reports the same time
+    parse_ms: f64,
+    /// Missing when parsing failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solve_ms: Option<f64>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Run(args) => run(args),
+        Command::Bench(args) => bench::bench(args),
+        Command::Verify(args) => verify::verify(args),
+    }
+}
+
+fn run(args: RunArgs) -> ExitCode {
+    let mut runs = Vec::new();
+    if args.all {
+        let Some(inputs) = inputs(&args.input_dir, None) else {
+            return ExitCode::FAILURE;
+        };
+        for (entry, input) in inputs {
+            runs.extend(solve(entry, args.part, input));
+        }
+    } else {
+        let day = args.day.expect("clap requires a day without --all");
+        let Some(entry) = aoc2023::day(day) else {
+            eprintln!("day {day} does not exist");
+            return ExitCode::FAILURE;
+        };
+        let input = match &args.input {
+            Some(path) if path.as_os_str() == "-" => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input).map(|_| input)
+            }
+            Some(path) => std::fs::read_to_string(path),
+            None => aoc2023::read_input(&args.input_dir, day),
+        };
+        match input {
+            Ok(input) => runs.extend(solve(entry, args.part, input.into())),
+            Err(error) => {
+                eprintln!("could not read the input for day {day}: {error}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    match args.format {
+        Format::Table => print_table(&runs),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&runs).unwrap()),
+    }
+
+    if runs.iter().any(|run| run.error.is_some()) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Reads the input of every day (or just `day`) with a finished part, skipping those that don't have one.
+/// Reports errors other than missing files and gives up.
+fn inputs(input_dir: &Path, day: Option<u8>) -> Option<Vec<(&'static DayEntry, Input)>> {
+    let mut inputs = Vec::new();
+    let days = DAYS.iter()
+        .filter(|entry| !entry.parts.is_empty() && day.is_none_or(|day| day == entry.number));
+    for entry in days {
+        match aoc2023::read_input(input_dir, entry.number) {
+            Ok(input) => inputs.push((entry, input.into())),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                eprintln!("skipping day {}: no input in {}", entry.number, input_dir.display());
+            }
+            Err(error) => {
+                eprintln!("could not read the input for day {}: {error}", entry.number);
+                return None;
+            }
+        }
+    }
+    Some(inputs)
+}
+
+/// The parts to solve: the one asked for, or every part the day has finished
+fn parts(entry: &DayEntry, part: Option<u8>) -> Vec<u8> {
+    match part {
+        Some(part) => vec![part],
+        None => entry.parts.to_vec(),
+    }
+}
+
+/// Parses the input once, then solves the requested part, or every part the day has finished
+fn solve(entry: &DayEntry, part: Option<u8>, input: Input) -> Vec<Run> {
+    let start = Instant::now();
+    let parsed = (entry.parse)(&input);
+    let parse_ms = ms(start.elapsed());
+
+    let parts = parts(entry, part);
+    let mut runs = Vec::with_capacity(parts.len());
+    for part in parts {
+        let solver = entry.solver(part).expect("clap only accepts parts 1 and 2");
+        let (result, solve_ms) = match &parsed {
+            Ok(parsed) => {
+                let start = Instant::now();
+                let result = solver(parsed, &Context::new());
+                (result, Some(ms(start.elapsed())))
+            }
+            Err(error) => (Err(error.clone()), None),
+        };
+        let (answer, error) = match result {
+            Ok(answer) => (Some(answer), None),
+            Err(error) => (None, Some(error.to_string())),
+        };
+        let elapsed_ms = parse_ms + solve_ms.unwrap_or(0.0);
+        runs.push(Run { day: entry.number, part, answer, error, elapsed_ms, parse_ms, solve_ms });
+    }
+    runs
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn print_table(runs: &[Run]) {
+    let answers: Vec<String> = runs.iter()
+        .map(|run| match (&run.answer, &run.error) {
+            (Some(answer), _) => answer.to_string(),
+            (_, Some(error)) => format!("error: {error}"),
+            (None, None) => String::new(),
+        })
+        .collect();
+    let width = answers.iter().map(|answer| answer.len()).max().unwrap_or(0).max("Answer".len());
+
+    println!("Day  Part  {:<width$}  {:>12}  {:>12}", "Answer", "Parse", "Solve");
+    for (run, answer) in runs.iter().zip(answers) {
+        let solve = run.solve_ms.map_or("-".to_string(), |solve_ms| format!("{solve_ms:.3} ms"));
+        println!("{:>3}  {:>4}  {:<width$}  {:>9.3} ms  {:>12}", run.day, run.part, answer, run.parse_ms, solve);
+    }
+}
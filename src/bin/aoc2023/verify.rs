@@ -0,0 +1,54 @@
+//! `aoc2023 verify`: solving every recorded part again and failing on any answer that changed
+
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Args;
+
+use aoc2023::Answers;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Where the `dayNN.txt` inputs are kept
+    #[arg(long, default_value = "input")]
+    input_dir: PathBuf,
+    /// The recorded answers, as `day part answer` lines
+    #[arg(long, default_value = "answers.txt")]
+    answers: PathBuf,
+}
+
+pub fn verify(args: VerifyArgs) -> ExitCode {
+    let answers = match Answers::read(&args.answers) {
+        Ok(answers) => answers,
+        Err(error) => {
+            eprintln!("could not read {}: {error}", args.answers.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    let checks = match aoc2023::verify(&args.input_dir, &answers) {
+        Ok(checks) => checks,
+        Err(error) => {
+            eprintln!("could not read the inputs in {}: {error}", args.input_dir.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = 0;
+    for check in &checks {
+        let status = match &check.result {
+            _ if check.passed() => "ok".to_string(),
+            Ok(answer) => format!("MISMATCH: expected {}, got {answer}", check.expected),
+            Err(error) => format!("ERROR: {error}"),
+        };
+        if !check.passed() {
+            failed += 1;
+        }
+        println!("day {:>2} part {}  {status}", check.day, check.part);
+    }
+
+    println!("{} checked, {failed} failed", checks.len());
+    if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
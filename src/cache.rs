@@ -0,0 +1,191 @@
+//! Answers to inputs that were solved before, so submitting one again doesn't run the solver again
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::BuildHasher,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{http::HeaderName, response::Html};
+
+use aoc2023::{solution_fragment, Answer, Input};
+
+/// Set to `hit` or `miss` on responses that solve something
+pub const X_CACHE: HeaderName = HeaderName::from_static("x-cache");
+
+pub fn header(hit: bool) -> [(HeaderName, &'static str); 1] {
+    [(X_CACHE, if hit { "hit" } else { "miss" })]
+}
+
+/// A bounded map from (day, part, normalized input) to the answer, dropping the least recently used
+/// entry when it's full and any entry older than the TTL. Only answers are kept, not errors.
+pub struct Cache {
+    capacity: usize,
+    ttl: Duration,
+    /// Keys the input hashes, so colliding inputs can't be made up ahead of time
+    hasher: RandomState,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// How the cache has done since it started
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    day: u8,
+    part: u8,
+    input: u64,
+}
+
+/// An answer, how long it took to get the first time, and the id it was stored under then
+#[derive(Clone)]
+pub struct Cached {
+    pub answer: Answer,
+    pub parse: Duration,
+    pub solve: Duration,
+    pub permalink: Option<String>,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<Key, Entry>,
+    /// Counts lookups and inserts, to tell which entry was used least recently
+    clock: u64,
+}
+
+struct Entry {
+    value: Cached,
+    stored: Instant,
+    used: u64,
+}
+
+impl Cache {
+    /// A cache that holds nothing when `capacity` is 0
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self { capacity, ttl, hasher: RandomState::new(), entries: Default::default(), hits: 0.into(), misses: 0.into() }
+    }
+
+    pub fn key(&self, day: u8, part: u8, input: &Input) -> Key {
+        Key { day, part, input: self.hasher.hash_one(input.as_str()) }
+    }
+
+    pub fn get(&self, key: &Key) -> Option<Cached> {
+        let found = self.lookup(key);
+        let counter = if found.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    fn lookup(&self, key: &Key) -> Option<Cached> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+        match entries.map.get_mut(key) {
+            Some(entry) if entry.stored.elapsed() < self.ttl => {
+                entry.used = clock;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.map.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: Key, value: Cached) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let used = entries.clock;
+        if !entries.map.contains_key(&key) && entries.map.len() >= self.capacity {
+            let ttl = self.ttl;
+            entries.map.retain(|_, entry| entry.stored.elapsed() < ttl);
+            // A linear scan, which is fine for the few thousand entries this is meant for
+            if entries.map.len() >= self.capacity {
+                let oldest = entries.map.iter().min_by_key(|(_, entry)| entry.used).map(|(key, _)| *key);
+                if let Some(oldest) = oldest {
+                    entries.map.remove(&oldest);
+                }
+            }
+        }
+        entries.map.insert(key, Entry { value, stored: Instant::now(), used });
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().map.len(),
+        }
+    }
+
+    /// Empties the cache, returning how many entries it had
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let cleared = entries.map.len();
+        entries.map.clear();
+        cleared
+    }
+}
+
+impl Cached {
+    pub fn fragment(self) -> Html<String> {
+        solution_fragment(Ok(self.answer), Some(self.solve), true, self.permalink.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(answer: i64) -> Cached {
+        Cached { answer: answer.into(), parse: Duration::ZERO, solve: Duration::ZERO, permalink: None }
+    }
+
+    fn answer(cache: &Cache, key: &Key) -> Option<Answer> {
+        cache.get(key).map(|cached| cached.answer)
+    }
+
+    #[test]
+    fn test_least_recently_used() {
+        let cache = Cache::new(2, Duration::from_secs(60));
+        let [a, b, c] = ["a", "b", "c"].map(|input| cache.key(1, 1, &input.into()));
+        cache.insert(a, cached(1));
+        cache.insert(b, cached(2));
+        assert_eq!(answer(&cache, &a), Some(Answer::Signed(1)));
+        // `b` hasn't been looked at since it went in, so it makes room
+        cache.insert(c, cached(3));
+        assert_eq!(answer(&cache, &b), None);
+        assert_eq!(answer(&cache, &a), Some(Answer::Signed(1)));
+        assert_eq!(answer(&cache, &c), Some(Answer::Signed(3)));
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (3, 1, 2));
+        assert_eq!(cache.clear(), 2);
+        assert_eq!(answer(&cache, &a), None);
+    }
+
+    #[test]
+    fn test_keys_and_expiry() {
+        let cache = Cache::new(10, Duration::from_secs(60));
+        assert!(cache.key(1, 1, &"x\r\n".into()) == cache.key(1, 1, &"x".into()));
+        assert!(cache.key(1, 1, &"x".into()) != cache.key(1, 2, &"x".into()));
+
+        let cache = Cache::new(10, Duration::ZERO);
+        let key = cache.key(1, 1, &"x".into());
+        cache.insert(key, cached(1));
+        assert_eq!(answer(&cache, &key), None);
+    }
+}
@@ -0,0 +1,244 @@
+//! Settings for the server. Each comes from a flag, then an environment variable, then the config file,
+//! then its default. The file is TOML with the flags' long names as keys, `_` instead of `-`:
+//!
+//! ```toml
+//! bind = "0.0.0.0"
+//! port = 8080
+//! static_dir = "/srv/aoc2023/static"
+//! log_level = "debug"
+//! log_format = "json"
+//! data_dir = "/var/lib/aoc2023"
+//! ```
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::NonZeroUsize,
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+use clap::Parser;
+use serde::Deserialize;
+use tracing::level_filters::LevelFilter;
+
+#[derive(Parser)]
+#[command(about = "Serves the Advent of Code 2023 solutions")]
+struct Cli {
+    /// TOML file to read settings from
+    #[arg(long, short, env = "AOC_CONFIG")]
+    config: Option<PathBuf>,
+    #[command(flatten)]
+    settings: Settings,
+}
+
+#[derive(clap::Args, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Settings {
+    /// Address to listen on [default: 127.0.0.1 in debug builds, 0.0.0.0 in release builds]
+    #[arg(long, env = "AOC_BIND")]
+    bind: Option<IpAddr>,
+    /// Port to listen on [default: 8080]
+    #[arg(long, short, env = "AOC_PORT")]
+    port: Option<u16>,
+    /// Serve `/static` from this directory instead of the files built into the binary,
+    /// to see changes without rebuilding [default: static in debug builds, built in for release]
+    #[arg(long, env = "AOC_STATIC_DIR")]
+    static_dir: Option<PathBuf>,
+    /// `off`, `error`, `warn`, `info`, `debug` or `trace` [default: info]
+    #[arg(long, env = "AOC_LOG")]
+    log_level: Option<String>,
+    /// Whether to log lines for people or JSON objects for a log collector [default: human]
+    #[arg(long, env = "AOC_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+    /// How many solves run at once [default: one per core]
+    #[arg(long, env = "AOC_SOLVE_CONCURRENCY")]
+    solve_concurrency: Option<usize>,
+    /// How long a solve can take before it's stopped [default: 10]
+    #[arg(long, env = "AOC_SOLVE_TIMEOUT_SECS")]
+    solve_timeout_secs: Option<f64>,
+    /// How many answers to remember, 0 for none [default: 1000]
+    #[arg(long, env = "AOC_CACHE_SIZE")]
+    cache_size: Option<usize>,
+    /// How long to remember an answer [default: 3600]
+    #[arg(long, env = "AOC_CACHE_TTL_SECS")]
+    cache_ttl_secs: Option<u64>,
+    /// How long running solves get to finish when the server is stopped [default: 10]
+    #[arg(long, env = "AOC_SHUTDOWN_GRACE_SECS")]
+    shutdown_grace_secs: Option<f64>,
+    /// Bearer token the admin endpoints require [default: none, so they're turned off]
+    #[arg(long, env = "AOC_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
+    /// Directory the shared results are kept in [default: data]
+    #[arg(long, env = "AOC_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    /// How many days a shared result stays up [default: 30]
+    #[arg(long, env = "AOC_RETENTION_DAYS")]
+    retention_days: Option<u64>,
+}
+
+impl Settings {
+    /// Fills in whatever isn't set here from `other`
+    fn or(self, other: Self) -> Self {
+        Self {
+            bind: self.bind.or(other.bind),
+            port: self.port.or(other.port),
+            static_dir: self.static_dir.or(other.static_dir),
+            log_level: self.log_level.or(other.log_level),
+            log_format: self.log_format.or(other.log_format),
+            solve_concurrency: self.solve_concurrency.or(other.solve_concurrency),
+            solve_timeout_secs: self.solve_timeout_secs.or(other.solve_timeout_secs),
+            cache_size: self.cache_size.or(other.cache_size),
+            cache_ttl_secs: self.cache_ttl_secs.or(other.cache_ttl_secs),
+            shutdown_grace_secs: self.shutdown_grace_secs.or(other.shutdown_grace_secs),
+            admin_token: self.admin_token.or(other.admin_token),
+            data_dir: self.data_dir.or(other.data_dir),
+            retention_days: self.retention_days.or(other.retention_days),
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Human,
+    Json,
+}
+
+pub struct Config {
+    pub addr: SocketAddr,
+    /// `None` to serve the files built into the binary
+    pub static_dir: Option<PathBuf>,
+    pub log_level: LevelFilter,
+    pub log_format: LogFormat,
+    pub solve_concurrency: usize,
+    pub solve_timeout: Duration,
+    pub cache_size: usize,
+    pub cache_ttl: Duration,
+    pub shutdown_grace: Duration,
+    pub admin_token: Option<String>,
+    pub data_dir: PathBuf,
+    pub retention: Duration,
+}
+
+/// Why the settings couldn't be put together
+#[derive(Debug)]
+pub enum ConfigError {
+    Read { path: PathBuf, error: std::io::Error },
+    Parse { path: PathBuf, error: toml::de::Error },
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { path, error } => write!(f, "could not read the config file {}: {error}", path.display()),
+            Self::Parse { path, error } => write!(f, "the config file {} is not valid: {error}", path.display()),
+            Self::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads the flags and environment, and the config file if they name one.
+    /// Bad flags print clap's usage and exit, like any other command line tool.
+    pub fn load() -> Result<Self, ConfigError> {
+        let cli = Cli::parse();
+        let file = match &cli.config {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|error| ConfigError::Read { path: path.clone(), error })?;
+                toml::from_str(&text).map_err(|error| ConfigError::Parse { path: path.clone(), error })?
+            }
+            None => Settings::default(),
+        };
+        Self::resolve(cli.settings.or(file))
+    }
+
+    fn resolve(settings: Settings) -> Result<Self, ConfigError> {
+        let default_bind = if cfg!(debug_assertions) { Ipv4Addr::LOCALHOST } else { Ipv4Addr::UNSPECIFIED };
+        let addr = SocketAddr::new(settings.bind.unwrap_or(default_bind.into()), settings.port.unwrap_or(8080));
+
+        let log_level = match settings.log_level {
+            Some(level) => level.parse().map_err(|_| {
+                ConfigError::Invalid(format!("the log level has to be off, error, warn, info, debug or trace, not {level:?}"))
+            })?,
+            None => LevelFilter::INFO,
+        };
+
+        let timeout = settings.solve_timeout_secs.unwrap_or(10.0);
+        let solve_timeout = Some(timeout)
+            .filter(|&timeout| timeout > 0.0)
+            .and_then(|timeout| Duration::try_from_secs_f64(timeout).ok())
+            .ok_or_else(|| {
+                ConfigError::Invalid(format!("the solve timeout has to be a positive number of seconds, not {timeout}"))
+            })?;
+        let grace = settings.shutdown_grace_secs.unwrap_or(10.0);
+        let shutdown_grace = Duration::try_from_secs_f64(grace).map_err(|_| {
+            ConfigError::Invalid(format!("the shutdown grace period has to be a number of seconds, not {grace}"))
+        })?;
+
+        let retention_days = settings.retention_days.unwrap_or(30);
+        if retention_days == 0 {
+            return Err(ConfigError::Invalid("results have to be kept for at least a day".into()));
+        }
+        let retention = retention_days
+            .checked_mul(86_400)
+            .map(Duration::from_secs)
+            .ok_or_else(|| ConfigError::Invalid(format!("results can't be kept for {retention_days} days")))?;
+
+        Ok(Self {
+            addr,
+            static_dir: settings.static_dir.or_else(|| cfg!(debug_assertions).then(|| "static".into())),
+            log_level,
+            log_format: settings.log_format.unwrap_or(LogFormat::Human),
+            solve_concurrency: settings.solve_concurrency
+                .unwrap_or_else(|| thread::available_parallelism().map_or(2, NonZeroUsize::get)),
+            solve_timeout,
+            cache_size: settings.cache_size.unwrap_or(1000),
+            cache_ttl: Duration::from_secs(settings.cache_ttl_secs.unwrap_or(3600)),
+            shutdown_grace,
+            admin_token: settings.admin_token,
+            data_dir: settings.data_dir.unwrap_or_else(|| "data".into()),
+            retention,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let file: Settings = toml::from_str("bind = \"10.0.0.1\"\nport = 9000\nlog_level = \"debug\"\nlog_format = \"json\"").unwrap();
+        let flags = Settings { port: Some(1234), ..Default::default() };
+        let config = Config::resolve(flags.or(file)).unwrap();
+        assert_eq!(config.addr, "10.0.0.1:1234".parse().unwrap());
+        assert_eq!(config.log_level, LevelFilter::DEBUG);
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(config.static_dir, cfg!(debug_assertions).then(|| PathBuf::from("static")));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(toml::from_str::<Settings>("prot = 80").is_err());
+        let settings = Settings { log_level: Some("loud".into()), ..Default::default() };
+        assert!(matches!(Config::resolve(settings), Err(ConfigError::Invalid(_))));
+        let settings = Settings { solve_timeout_secs: Some(0.0), ..Default::default() };
+        assert!(matches!(Config::resolve(settings), Err(ConfigError::Invalid(_))));
+        let settings = Settings { solve_timeout_secs: Some(1e30), ..Default::default() };
+        assert!(matches!(Config::resolve(settings), Err(ConfigError::Invalid(_))));
+        let settings = Settings { shutdown_grace_secs: Some(f64::MAX), ..Default::default() };
+        assert!(matches!(Config::resolve(settings), Err(ConfigError::Invalid(_))));
+        let settings = Settings { shutdown_grace_secs: Some(-1.0), ..Default::default() };
+        assert!(matches!(Config::resolve(settings), Err(ConfigError::Invalid(_))));
+        let settings = Settings { retention_days: Some(0), ..Default::default() };
+        assert!(matches!(Config::resolve(settings), Err(ConfigError::Invalid(_))));
+        let settings = Settings { retention_days: Some(u64::MAX), ..Default::default() };
+        assert!(matches!(Config::resolve(settings), Err(ConfigError::Invalid(_))));
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+use crate::SolveError;
+
+/// Handed to every solver alongside its input, so a long-running one can notice it's no longer wanted
+/// and say how far along it is. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    cancelled: AtomicBool,
+    done: AtomicU64,
+    /// Zero until the solver reports progress
+    total: AtomicU64,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the solver to stop. It only does at its next `check`.
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fails with `SolveError::Cancelled` once the solve has been cancelled. Cheap enough to call
+    /// on every pass through a long loop.
+    pub fn check(&self) -> Result<(), SolveError> {
+        if self.is_cancelled() {
+            Err(SolveError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reports that `done` out of `total` steps are finished, in whatever unit suits the solver.
+    /// Just a couple of stores, so it's fine to call often.
+    pub fn progress(&self, done: u64, total: u64) {
+        self.shared.done.store(done, Ordering::Relaxed);
+        self.shared.total.store(total, Ordering::Relaxed);
+    }
+
+    /// The latest `(done, total)` reported by the solver, if it has reported any
+    pub fn current_progress(&self) -> Option<(u64, u64)> {
+        let total = self.shared.total.load(Ordering::Relaxed);
+        let done = self.shared.done.load(Ordering::Relaxed);
+        (total > 0).then_some((done.min(total), total))
+    }
+}
@@ -0,0 +1,141 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+/// Why a solver could not produce an answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The input doesn't look like the puzzle's, pointing at the offending spot.
+    Parse {
+        /// 1-based line of the input
+        line: usize,
+        /// 1-based column, counted in characters
+        column: usize,
+        /// What should have been there instead
+        expected: String,
+    },
+    /// The part hasn't been solved yet
+    Unimplemented { part: u8 },
+    /// Solving took longer than allowed, so it was cancelled
+    Timeout { limit: Duration },
+    /// The solver noticed it was cancelled and stopped
+    Cancelled,
+    /// The solver panicked, with the panic's message
+    Panicked(String),
+    /// The input parsed, but the numbers it leads to don't fit in the ones the solver works with
+    Overflow,
+}
+
+impl SolveError {
+    /// Points at `span`, which has to be a slice of `s`.
+    pub fn at(s: &str, span: &str, expected: impl Into<String>) -> Self {
+        let (line, column) = locate(s, span);
+        Self::Parse {
+            line,
+            column,
+            expected: expected.into(),
+        }
+    }
+
+    /// Points at the very end of `s`, for when the input stops too early.
+    pub fn at_end(s: &str, expected: impl Into<String>) -> Self {
+        Self::at(s, &s[s.len()..], expected)
+    }
+
+    /// A short name for the kind of error, like `parse` or `timeout`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Parse { .. } => "parse",
+            Self::Unimplemented { .. } => "unimplemented",
+            Self::Timeout { .. } => "timeout",
+            Self::Cancelled => "cancelled",
+            Self::Panicked(_) => "panic",
+            Self::Overflow => "overflow",
+        }
+    }
+
+    /// Re-bases an error that was reported relative to `span` onto `s`, the text `span` was sliced from.
+    pub fn within(self, s: &str, span: &str) -> Self {
+        let Self::Parse { line: inner_line, column: inner_column, expected } = self else {
+            return self;
+        };
+        let (line, column) = locate(s, span);
+        if inner_line == 1 {
+            Self::Parse {
+                line,
+                column: column + inner_column - 1,
+                expected,
+            }
+        } else {
+            Self::Parse {
+                line: line + inner_line - 1,
+                column: inner_column,
+                expected,
+            }
+        }
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, column, expected } => {
+                write!(f, "bad input at line {line}, column {column}: expected {expected}")
+            }
+            Self::Unimplemented { part } => write!(f, "part {part} is not finished yet"),
+            Self::Timeout { limit } => write!(f, "took longer than {limit:?}, so it was stopped"),
+            Self::Cancelled => write!(f, "the solve was cancelled"),
+            Self::Panicked(message) => write!(f, "the solver crashed: {message}"),
+            Self::Overflow => write!(f, "the numbers in the input get too big to work with"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// The message a panic was started with, when it's a string like it is for `panic!` and friends
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "no message".to_owned(),
+    }
+}
+
+/// Parses `span` (a slice of `s`) and points at it if that fails.
+pub fn parse_at<T: FromStr>(s: &str, span: &str, expected: &str) -> Result<T, SolveError> {
+    span.parse().map_err(|_| SolveError::at(s, span, expected))
+}
+
+fn locate(s: &str, span: &str) -> (usize, usize) {
+    // Both are slices of the same buffer, so the distance between their pointers is the offset.
+    // Anything else falls back to the start of `s`.
+    let offset = (span.as_ptr() as usize)
+        .checked_sub(s.as_ptr() as usize)
+        .filter(|&offset| offset <= s.len() && s.is_char_boundary(offset))
+        .unwrap_or(0);
+    let before = &s[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |ind| ind + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_at() {
+        let input = "abc\nde fg\n";
+        let err = SolveError::at(input, &input[7..9], "something");
+        assert_eq!(err.to_string(), "bad input at line 2, column 4: expected something");
+    }
+
+    #[test]
+    fn test_within() {
+        let input = "first\nsecond: 12 x4";
+        let line = input.lines().nth(1).unwrap();
+        let item = &line[11..];
+        let err = SolveError::at(item, &item[1..], "a digit").within(line, item).within(input, line);
+        assert_eq!(err, SolveError::Parse { line: 2, column: 13, expected: "a digit".into() });
+    }
+}
@@ -0,0 +1,328 @@
+//! Dense two-dimensional grids, for the many puzzles drawn on a map
+
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use crate::{Input, SolveError};
+
+/// A cell's position, counted from 0 at the top left
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Pos {
+    pub const fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+
+    /// The position one step in `dir`, unless that would go above or left of the origin.
+    /// Use `Grid::step` to stay inside a grid.
+    pub fn step(self, dir: Dir) -> Option<Self> {
+        let (row, col) = dir.offset();
+        Some(Self {
+            row: self.row.checked_add_signed(row)?,
+            col: self.col.checked_add_signed(col)?,
+        })
+    }
+
+    pub fn manhattan(self, other: Self) -> usize {
+        self.row.abs_diff(other.row) + self.col.abs_diff(other.col)
+    }
+}
+
+/// One of the four ways to move between cells that share a side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Dir {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Dir {
+    /// Clockwise from `Up`
+    pub const ALL: [Dir; 4] = [Dir::Up, Dir::Right, Dir::Down, Dir::Left];
+
+    /// `(rows, columns)` moved by one step
+    pub const fn offset(self) -> (isize, isize) {
+        match self {
+            Dir::Up => (-1, 0),
+            Dir::Right => (0, 1),
+            Dir::Down => (1, 0),
+            Dir::Left => (0, -1),
+        }
+    }
+
+    pub const fn turn_right(self) -> Self {
+        match self {
+            Dir::Up => Dir::Right,
+            Dir::Right => Dir::Down,
+            Dir::Down => Dir::Left,
+            Dir::Left => Dir::Up,
+        }
+    }
+
+    pub const fn turn_left(self) -> Self {
+        match self {
+            Dir::Up => Dir::Left,
+            Dir::Right => Dir::Up,
+            Dir::Down => Dir::Right,
+            Dir::Left => Dir::Down,
+        }
+    }
+
+    pub const fn reverse(self) -> Self {
+        self.turn_right().turn_right()
+    }
+
+    pub const fn is_horizontal(self) -> bool {
+        matches!(self, Dir::Left | Dir::Right)
+    }
+
+    /// Its place in `ALL`, handy for keeping something per direction in an array
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// `(rows, columns)` to every cell around one, diagonals included, clockwise from the top left
+const AROUND: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1)];
+
+/// A rectangle of cells, stored row by row
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    rows: usize,
+    cols: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(rows: usize, cols: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self { cells: vec![fill; rows * cols], rows, cols }
+    }
+
+    /// Parses one cell per character of every line, pointing errors at the first character `cell` turns down
+    /// or at the first line that isn't as long as the first.
+    pub fn parse(input: &Input, expected: &str, mut cell: impl FnMut(u8) -> Option<T>) -> Result<Self, SolveError> {
+        let lines = input.lines();
+        let cols = lines.first().map_or(0, |line| line.len());
+        let mut cells = Vec::with_capacity(lines.len() * cols);
+        for line in &lines {
+            if line.len() != cols {
+                return Err(SolveError::at(input, line, format!("a row {cols} characters long, like the first")));
+            }
+            for (col, b) in line.bytes().enumerate() {
+                match cell(b) {
+                    Some(value) => cells.push(value),
+                    None => {
+                        let len = line[col..].chars().next().map_or(1, char::len_utf8);
+                        return Err(SolveError::at(input, &line[col..col + len], expected));
+                    }
+                }
+            }
+        }
+        Ok(Self { cells, rows: lines.len(), cols })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.row < self.rows && pos.col < self.cols
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos).then(|| &self.cells[pos.row * self.cols + pos.col])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        self.contains(pos).then(|| &mut self.cells[pos.row * self.cols + pos.col])
+    }
+
+    /// The position one step in `dir`, if it's still in the grid
+    pub fn step(&self, pos: Pos, dir: Dir) -> Option<Pos> {
+        pos.step(dir).filter(|&next| self.contains(next))
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn column(&self, col: usize) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        assert!(col < self.cols, "column {col} is outside a grid {} wide", self.cols);
+        self.cells[col..].iter().step_by(self.cols)
+    }
+
+    /// Every position, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let cols = self.cols;
+        (0..self.rows).flat_map(move |row| (0..cols).map(move |col| Pos::new(row, col)))
+    }
+
+    /// Every cell with its position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    /// The cells sharing a side with `pos`, clockwise from above
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Dir::ALL.into_iter().filter_map(move |dir| self.step(pos, dir))
+    }
+
+    /// The cells sharing a side or a corner with `pos`, clockwise from the top left
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        AROUND.into_iter().filter_map(move |(row, col)| {
+            let next = Pos::new(pos.row.checked_add_signed(row)?, pos.col.checked_add_signed(col)?);
+            self.contains(next).then_some(next)
+        })
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid { cells: self.cells.iter().map(f).collect(), rows: self.rows, cols: self.cols }
+    }
+
+    /// Flips the grid over its main diagonal, so rows become columns
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.rearranged(self.cols, self.rows, |pos| Pos::new(pos.col, pos.row))
+    }
+
+    /// Turns the grid a quarter clockwise, so the first column becomes the first row
+    pub fn rotate_right(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.rearranged(self.cols, self.rows, |pos| Pos::new(self.rows - 1 - pos.col, pos.row))
+    }
+
+    /// Turns the grid a quarter anticlockwise, so the last column becomes the first row
+    pub fn rotate_left(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.rearranged(self.cols, self.rows, |pos| Pos::new(pos.col, self.cols - 1 - pos.row))
+    }
+
+    /// Builds a `rows` by `cols` grid whose cell at each position is this one's cell at `from(position)`
+    fn rearranged(&self, rows: usize, cols: usize, from: impl Fn(Pos) -> Pos) -> Self
+    where
+        T: Clone,
+    {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for row in 0..rows {
+            for col in 0..cols {
+                cells.push(self[from(Pos::new(row, col))].clone());
+            }
+        }
+        Self { cells, rows, cols }
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{pos:?} is outside a {}x{} grid", self.rows, self.cols))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let (rows, cols) = (self.rows, self.cols);
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{pos:?} is outside a {rows}x{cols} grid"))
+    }
+}
+
+/// Draws a grid of bytes the way it was parsed, for debugging
+impl fmt::Display for Grid<u8> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.rows {
+            writeln!(f, "{}", String::from_utf8_lossy(self.row(row)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(s: &str) -> Grid<u8> {
+        Input::new(s).grid().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = Input::new("ab\ncd\nefg");
+        let err = input.grid().unwrap_err();
+        assert_eq!(err, SolveError::Parse { line: 3, column: 1, expected: "a row 2 characters long, like the first".into() });
+
+        let input = Input::new("..\n.x");
+        let err = Grid::parse(&input, "`.`", |b| (b == b'.').then_some(())).unwrap_err();
+        assert_eq!(err, SolveError::Parse { line: 2, column: 2, expected: "`.`".into() });
+    }
+
+    #[test]
+    fn test_slicing() {
+        let grid = grid("abc\ndef");
+        assert_eq!((grid.rows(), grid.cols()), (2, 3));
+        assert_eq!(grid.row(1), b"def");
+        assert_eq!(grid.column(2).copied().collect::<Vec<_>>(), b"cf");
+        assert_eq!(grid[Pos::new(1, 0)], b'd');
+        assert_eq!(grid.get(Pos::new(2, 0)), None);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = grid("abc\ndef\nghi");
+        let around = |pos, eight| -> String {
+            let cells: Vec<u8> = if eight {
+                grid.neighbours8(pos).map(|pos| grid[pos]).collect()
+            } else {
+                grid.neighbours4(pos).map(|pos| grid[pos]).collect()
+            };
+            String::from_utf8(cells).unwrap()
+        };
+        assert_eq!(around(Pos::new(1, 1), false), "bfhd");
+        assert_eq!(around(Pos::new(1, 1), true), "abcfihgd");
+        assert_eq!(around(Pos::new(0, 0), false), "bd");
+        assert_eq!(around(Pos::new(2, 2), true), "efh");
+    }
+
+    #[test]
+    fn test_rearranging() {
+        let grid = grid("abc\ndef");
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.rotate_right().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate_left().to_string(), "cf\nbe\nad\n");
+        assert_eq!(grid.rotate_right().rotate_left(), grid);
+    }
+
+    #[test]
+    fn test_dir() {
+        for dir in Dir::ALL {
+            assert_eq!(dir.turn_left().turn_right(), dir);
+            assert_eq!(dir.reverse().reverse(), dir);
+            assert_eq!(Dir::ALL[dir.index()], dir);
+        }
+        assert_eq!(Dir::Up.turn_left(), Dir::Left);
+        assert_eq!(Pos::new(0, 3).step(Dir::Up), None);
+        assert_eq!(Pos::new(0, 3).step(Dir::Left), Some(Pos::new(0, 2)));
+    }
+}
@@ -0,0 +1,270 @@
+//! A small syntax highlighter for Rust, enough to colour the solutions' source on the site
+//! without pulling in a grammar
+
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Plain,
+    Keyword,
+    Type,
+    Macro,
+    String,
+    Number,
+    Comment,
+    Lifetime,
+    Attribute,
+}
+
+impl Class {
+    fn css(self) -> Option<&'static str> {
+        match self {
+            Class::Plain => None,
+            Class::Keyword => Some("hl-keyword"),
+            Class::Type => Some("hl-type"),
+            Class::Macro => Some("hl-macro"),
+            Class::String => Some("hl-string"),
+            Class::Number => Some("hl-number"),
+            Class::Comment => Some("hl-comment"),
+            Class::Lifetime => Some("hl-lifetime"),
+            Class::Attribute => Some("hl-attribute"),
+        }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+/// Highlights `source` as HTML, one string per line, so each line can get its own anchor
+pub fn highlight(source: &str) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for (class, text) in tokens(source) {
+        for (n, piece) in text.split('\n').enumerate() {
+            if n > 0 {
+                lines.push(String::new());
+            }
+            let piece = piece.trim_end_matches('\r');
+            if piece.is_empty() {
+                continue;
+            }
+            let line = lines.last_mut().expect("there's always a line");
+            match class.css() {
+                Some(css) => {
+                    let _ = write!(line, "<span class=\"{css}\">{}</span>", escape(piece));
+                }
+                None => line.push_str(&escape(piece)),
+            }
+        }
+    }
+    if source.ends_with('\n') {
+        lines.pop();
+    }
+    lines
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Splits `source` into runs of one class, which put back together are `source` again
+fn tokens(source: &str) -> Vec<(Class, &str)> {
+    let mut tokens: Vec<(Class, &str)> = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        let rest = &source[i..];
+        let first = rest.chars().next().expect("not at the end yet");
+        let (class, len) = if rest.starts_with("//") {
+            (Class::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if rest.starts_with("/*") {
+            (Class::Comment, block_comment_len(rest))
+        } else if let Some(len) = string_len(rest) {
+            (Class::String, len)
+        } else if let Some(len) = char_len(rest) {
+            (Class::String, len)
+        } else if first == '\'' {
+            (Class::Lifetime, 1 + ident_len(&rest[1..]))
+        } else if first.is_ascii_digit() {
+            (Class::Number, number_len(rest))
+        } else if first.is_alphabetic() || first == '_' {
+            let len = ident_len(rest);
+            let word = &rest[..len];
+            let after = &rest[len..];
+            if KEYWORDS.contains(&word) {
+                (Class::Keyword, len)
+            } else if after.starts_with('!') && !after.starts_with("!=") {
+                (Class::Macro, len + 1)
+            } else if word.starts_with(|c: char| c.is_uppercase()) {
+                (Class::Type, len)
+            } else {
+                (Class::Plain, len)
+            }
+        } else if rest.starts_with("#[") || rest.starts_with("#![") {
+            (Class::Attribute, attribute_len(rest))
+        } else {
+            (Class::Plain, first.len_utf8())
+        };
+
+        let text = &source[i..i + len];
+        match tokens.last_mut() {
+            // Keeps the output from being one span per space
+            Some((last, previous)) if *last == class && class == Class::Plain => {
+                *previous = &source[i - previous.len()..i + len];
+            }
+            _ => tokens.push((class, text)),
+        }
+        i += len;
+    }
+    tokens
+}
+
+fn ident_len(s: &str) -> usize {
+    s.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(s.len())
+}
+
+/// Digits, suffixes and underscores, and a decimal point when a digit follows it (so `0..4` is two numbers)
+fn number_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut len = 0;
+    while len < bytes.len() {
+        let b = bytes[len];
+        let decimal_point = b == b'.' && bytes.get(len + 1).is_some_and(u8::is_ascii_digit);
+        if !(b.is_ascii_alphanumeric() || b == b'_' || decimal_point) {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+/// Block comments nest in Rust
+fn block_comment_len(s: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        if s[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if s[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += s[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    s.len()
+}
+
+/// The length of the string literal `s` starts with, if it does: `"…"`, `b"…"` or raw, like `r#"…"#`
+fn string_len(s: &str) -> Option<usize> {
+    let after_b = s.strip_prefix('b').unwrap_or(s);
+    if let Some(raw) = after_b.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let body = raw[hashes..].strip_prefix('"')?;
+        let end = format!("\"{}", "#".repeat(hashes));
+        let len = body.find(&end).map_or(body.len(), |at| at + end.len());
+        return Some(s.len() - body.len() + len);
+    }
+    let body = after_b.strip_prefix('"')?;
+    let mut chars = body.char_indices();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Some(s.len() - body.len() + at + 1),
+            _ => {}
+        }
+    }
+    Some(s.len())
+}
+
+/// The length of the character literal `s` starts with, if it does, like `'a'`, `b'\n'` or `'\u{2603}'`,
+/// as opposed to a lifetime
+fn char_len(s: &str) -> Option<usize> {
+    let prefix = usize::from(s.starts_with("b'"));
+    let body = s[prefix..].strip_prefix('\'')?;
+    let first = body.chars().next()?;
+    let inner = if first == '\\' {
+        2 + body.get(2..)?.find('\'')?
+    } else if body[first.len_utf8()..].starts_with('\'') {
+        first.len_utf8()
+    } else {
+        return None;
+    };
+    Some(prefix + 1 + inner + 1)
+}
+
+/// Attributes can have brackets inside, like `#[cfg(any(test, feature = "x"))]`
+fn attribute_len(s: &str) -> usize {
+    let mut depth = 0;
+    for (at, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return at + 1;
+                }
+            }
+            '\n' => return at,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(source: &str) -> Vec<(Class, &str)> {
+        tokens(source).into_iter().filter(|(class, _)| *class != Class::Plain).collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            classes("fn f<'a>(s: &'a str) -> Vec<u8> { vec![b'x', '\\''] }"),
+            [
+                (Class::Keyword, "fn"),
+                (Class::Lifetime, "'a"),
+                (Class::Lifetime, "'a"),
+                (Class::Type, "Vec"),
+                (Class::Macro, "vec!"),
+                (Class::String, "b'x'"),
+                (Class::String, "'\\''"),
+            ]
+        );
+        assert_eq!(
+            classes("#[derive(Debug)] let x = r#\"a\"b\"# // c"),
+            [
+                (Class::Attribute, "#[derive(Debug)]"),
+                (Class::Keyword, "let"),
+                (Class::String, "r#\"a\"b\"#"),
+                (Class::Comment, "// c"),
+            ]
+        );
+        assert_eq!(classes("0..4 1.5 x != y"), [(Class::Number, "0"), (Class::Number, "4"), (Class::Number, "1.5")]);
+        assert_eq!(classes("a /* b /* c */ d */ e"), [(Class::Comment, "/* b /* c */ d */")]);
+        let source = include_str!("solutions/day19.rs");
+        assert_eq!(tokens(source).iter().map(|(_, text)| *text).collect::<String>(), source);
+    }
+
+    #[test]
+    fn test_lines() {
+        let lines = highlight("let s = \"a\nb<\";\n");
+        assert_eq!(
+            lines,
+            [
+                "<span class=\"hl-keyword\">let</span> s = <span class=\"hl-string\">&quot;a</span>",
+                "<span class=\"hl-string\">b&lt;&quot;</span>;",
+            ]
+        );
+    }
+}
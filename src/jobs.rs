@@ -0,0 +1,147 @@
+//! Solves that carry on in the background while the page follows them over server-sent events
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    convert::Infallible,
+    hash::BuildHasher,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html,
+    },
+};
+use minijinja::context;
+use futures_util::{stream, Stream};
+use serde::Serialize;
+use tokio::{sync::watch, time};
+use tracing::Instrument;
+
+use aoc2023::{solution_fragment, templates, Answer, Context, ParsedInput, Pool, SolveError, Solver};
+
+use crate::AppState;
+
+/// How often a running job's progress is looked at
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// How long a finished job sticks around for the page to collect its answer
+const KEEP_FINISHED: Duration = Duration::from_secs(60);
+
+type Outcome = Option<Solved>;
+
+/// What a finished job came up with, how long it took, and where the answer can be shared from
+#[derive(Clone)]
+pub struct Solved {
+    pub result: Result<Answer, SolveError>,
+    pub elapsed: Duration,
+    pub permalink: Option<String>,
+}
+
+impl Solved {
+    pub fn fragment(self) -> Html<String> {
+        solution_fragment(self.result, Some(self.elapsed), false, self.permalink.as_deref())
+    }
+}
+
+#[derive(Default)]
+pub struct Jobs {
+    count: AtomicU64,
+    /// Keys the ids, so they can't be guessed from one another
+    ids: RandomState,
+    running: Mutex<HashMap<String, Arc<Job>>>,
+}
+
+pub struct Job {
+    pub id: String,
+    cx: Context,
+    outcome: watch::Receiver<Outcome>,
+}
+
+impl Jobs {
+    /// Starts solving in the background under the current span, calling `on_done` once it's solved (or failed),
+    /// before the page gets the answer
+    pub fn start(
+        self: &Arc<Self>,
+        pool: &Pool,
+        solver: Solver,
+        parsed: ParsedInput,
+        on_done: impl FnOnce(&mut Solved) + Send + 'static,
+    ) -> Arc<Job> {
+        let id = format!("{:016x}", self.ids.hash_one(self.count.fetch_add(1, Ordering::Relaxed)));
+        let cx = Context::new();
+        let (sender, outcome) = watch::channel(None);
+        let job = Arc::new(Job { id: id.clone(), cx: cx.clone(), outcome });
+        self.running.lock().unwrap().insert(id.clone(), job.clone());
+
+        let (jobs, pool) = (self.clone(), pool.clone());
+        tokio::spawn(async move {
+            let start = Instant::now();
+            let result = pool.solve_with(solver, parsed, cx).await;
+            let mut solved = Solved { result, elapsed: start.elapsed(), permalink: None };
+            on_done(&mut solved);
+            sender.send_replace(Some(solved));
+            time::sleep(KEEP_FINISHED).await;
+            jobs.running.lock().unwrap().remove(&id);
+        }.in_current_span());
+        job
+    }
+
+    fn get(&self, id: &str) -> Option<Arc<Job>> {
+        self.running.lock().unwrap().get(id).cloned()
+    }
+}
+
+impl Job {
+    /// Waits up to `limit` for the answer, so quick solves don't need a round trip through the event stream
+    pub async fn wait(&self, limit: Duration) -> Outcome {
+        let mut outcome = self.outcome.clone();
+        let _ = time::timeout(limit, outcome.wait_for(Option::is_some)).await;
+        let result = outcome.borrow().clone();
+        result
+    }
+}
+
+#[derive(Serialize, PartialEq)]
+struct Progress {
+    done: u64,
+    total: u64,
+}
+
+/// `progress` events with `{"done", "total"}` whenever the solver reports some, then one `done` event
+/// holding the solution fragment, after which the stream ends.
+pub async fn events(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let job = state.jobs.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let events = stream::unfold((Some(job), None), |(job, mut last)| async move {
+        let job = job?;
+        let mut outcome = job.outcome.clone();
+        loop {
+            if let Some(solved) = outcome.borrow_and_update().clone() {
+                let event = Event::default().event("done").data(solved.fragment().0);
+                return Some((Ok(event), (None, last)));
+            }
+            let progress = job.cx.current_progress().map(|(done, total)| Progress { done, total });
+            if progress.is_some() && progress != last {
+                let event = Event::default().event("progress").json_data(&progress).unwrap();
+                last = progress;
+                return Some((Ok(event), (Some(job), last)));
+            }
+            if let Ok(Err(_)) = time::timeout(PROGRESS_INTERVAL, outcome.changed()).await {
+                // The job's task went away without an answer, so the solver panicked
+                let crashed = templates::render("templates/error.html", context!(message => "the solver crashed"));
+                return Some((Ok(Event::default().event("done").data(crashed)), (None, last)));
+            }
+        }
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
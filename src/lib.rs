@@ -1,31 +1,101 @@
-#![allow(async_fn_in_trait)]
-
+mod answers;
+mod context;
+mod error;
+pub mod grid;
+mod pool;
 mod solutions;
+pub mod templates;
+pub use answers::*;
+pub use context::*;
+pub use error::*;
+pub use pool::*;
 pub use solutions::*;
 
-use axum::{response::Html, Form};
+use std::{fmt, io, ops::Deref, path::Path, str::FromStr, time::Duration};
+
+use axum::response::Html;
+use minijinja::context;
+use num::BigInt;
 use serde::{Deserialize, Serialize};
 
-#[macro_export]
-macro_rules! layout {
-    ($layout_name:literal, $($rest:expr),+) => {
-        minijinja::render!(
-            include_str!($layout_name),
-            inner_content => $crate::layout!($($rest),+)
-        )
-    };
-    ($inner:expr) => {
-        $inner
-    };
+use grid::Grid;
+
+/// Reads `dayNN.txt` from `dir`, where puzzle inputs are kept
+pub fn read_input(dir: impl AsRef<Path>, day: u8) -> io::Result<String> {
+    std::fs::read_to_string(dir.as_ref().join(format!("day{day:02}.txt")))
 }
 
-pub fn read_input(day: &str) -> String {
-    std::fs::read_to_string(format!("input/day{day}.txt"))
-        .unwrap()
+/// A puzzle input with the differences between where it came from smoothed out: no byte order mark,
+/// `\n` line endings, and no trailing whitespace on any line, blank lines at the start, or newlines at the end.
+/// It derefs to the normalized text, which is what errors should point into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input(String);
+
+impl Input {
+    pub fn new(raw: &str) -> Self {
+        let raw = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+        let mut text = String::with_capacity(raw.len());
+        for line in raw.split('\n') {
+            // `trim_end` also takes care of the `\r` of a CRLF
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        let start = text.len() - text.trim_start_matches('\n').len();
+        text.truncate(text.trim_end().len());
+        text.drain(..start.min(text.len()));
+        Self(text)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn lines(&self) -> Vec<&str> {
+        self.0.lines().collect()
+    }
+
+    /// The blocks of lines between blank lines
+    pub fn sections(&self) -> Vec<&str> {
+        self.0
+            .split("\n\n")
+            .map(|section| section.trim_matches('\n'))
+            .filter(|section| !section.is_empty())
+            .collect()
+    }
+
+    /// Parses every line, pointing errors at the line they came from.
+    pub fn parse_lines<T: FromStr<Err = SolveError>>(&self) -> Result<Vec<T>, SolveError> {
+        self.0
+            .lines()
+            .map(|line| line.parse().map_err(|err: SolveError| err.within(&self.0, line)))
+            .collect()
+    }
+
+    /// The lines as a grid of bytes, checking that they're all as long as the first.
+    /// `Grid::parse` turns the bytes into something more useful along the way.
+    pub fn grid(&self) -> Result<Grid<u8>, SolveError> {
+        Grid::parse(self, "any character", Some)
+    }
 }
 
-pub fn lines(s: &str) -> Vec<&str> {
-    s.trim().split('\n').collect()
+impl From<&str> for Input {
+    fn from(raw: &str) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl From<String> for Input {
+    fn from(raw: String) -> Self {
+        Self::new(&raw)
+    }
+}
+
+impl Deref for Input {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -33,24 +103,132 @@ pub struct PartInput {
     pub input: String
 }
 
-pub trait Day {
-    async fn part1(_input: String) -> String {
-        "Part 1 not finished :(".into()
+/// A puzzle's answer, keeping the type the solver came up with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum Answer {
+    Signed(i64),
+    Unsigned(u64),
+    /// Serialized as a string so JSON consumers don't lose precision
+    #[serde(with = "big_int")]
+    Big(BigInt),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Signed(n) => n.fmt(f),
+            Self::Unsigned(n) => n.fmt(f),
+            Self::Big(n) => n.fmt(f),
+            Self::Text(s) => s.fmt(f),
+        }
+    }
+}
+
+macro_rules! answer_from {
+    ($variant:ident: $($t:ty),+) => {
+        $(impl From<$t> for Answer {
+            fn from(value: $t) -> Self {
+                Self::$variant(value.into())
+            }
+        })+
+    };
+}
+
+answer_from!(Signed: i8, i16, i32, i64);
+answer_from!(Unsigned: u8, u16, u32, u64);
+answer_from!(Big: BigInt);
+answer_from!(Text: String, &str);
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Self::Unsigned(value as u64)
+    }
+}
+
+impl From<isize> for Answer {
+    fn from(value: isize) -> Self {
+        Self::Signed(value as i64)
+    }
+}
+
+mod big_int {
+    use num::BigInt;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
     }
-    async fn part2(_input: String) -> String {
-        "Part 2 not finished :(".into()
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
     }
 }
 
-pub trait DayExt: Day {
-    async fn part1_ext(Form(input): Form<PartInput>) -> Html<String> {
-        Html(<Self as Day>::part1(input.input).await)
+/// An example input from the puzzle text, with the answers the text gives for it
+pub struct Example {
+    pub input: &'static str,
+    /// `None` when the text doesn't give this part's answer for this input
+    pub part1: Option<&'static str>,
+    pub part2: Option<&'static str>,
+}
+
+/// A day's puzzle. The input is parsed once, and both parts work from what `parse` made of it.
+/// Parts that aren't overridden report `SolveError::Unimplemented`,
+/// and the day's entry in `DAYS` should list only the parts that are.
+///
+/// Parsing and parts run on a blocking thread. A part that can take a while should call `cx.check()?`
+/// every so often, so it stops when it's cancelled.
+pub trait Day {
+    /// What the parts work from. It's kept around between solves, so it can't borrow from the input.
+    type Parsed: Send + Sync + 'static;
+
+    /// The examples from the puzzle text, checked by the tests and offered on the day's page
+    const EXAMPLES: &'static [Example] = &[];
+
+    fn parse(input: &Input) -> Result<Self::Parsed, SolveError>;
+
+    fn part1(_parsed: &Self::Parsed, _cx: &Context) -> Result<Answer, SolveError> {
+        Err(SolveError::Unimplemented { part: 1 })
+    }
+    fn part2(_parsed: &Self::Parsed, _cx: &Context) -> Result<Answer, SolveError> {
+        Err(SolveError::Unimplemented { part: 2 })
     }
+}
 
-    async fn part2_ext(Form(input): Form<PartInput>) -> Html<String> {
-        Html(<Self as Day>::part2(input.input).await)
+/// Renders a solver's result as the fragment that goes into `#solution1`/`#solution2`,
+/// along with how long solving took when that's known, whether the answer was remembered from before,
+/// and the id of the stored result to link to, when it was stored.
+pub fn solution_fragment(
+    result: Result<Answer, SolveError>,
+    elapsed: Option<Duration>,
+    cached: bool,
+    permalink: Option<&str>,
+) -> Html<String> {
+    match result {
+        Ok(answer) => Html(templates::render(
+            "templates/answer.html",
+            context!(
+                answer => answer.to_string(),
+                elapsed_ms => elapsed.map(|elapsed| format!("{:.3}", elapsed.as_secs_f64() * 1000.0)),
+                cached => cached,
+                permalink => permalink
+            ),
+        )),
+        Err(error) => Html(templates::render("templates/error.html", context!(message => error.to_string()))),
     }
 }
 
-impl<T> DayExt for T where T: Day { }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn test_input_normalized() {
+        let input = Input::new("\u{feff}\r\n\r\nab  \r\ncd\r\n  \r\n\r\nef\t\r\n\r\n");
+        assert_eq!(input.as_str(), "ab\ncd\n\n\nef");
+        assert_eq!(input.lines(), ["ab", "cd", "", "", "ef"]);
+        assert_eq!(input.sections(), ["ab\ncd", "ef"]);
+    }
+}
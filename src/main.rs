@@ -1,20 +1,78 @@
-use std::{fs, net::SocketAddr};
+use std::{
+    io,
+    future::IntoFuture,
+    net::SocketAddr,
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
-    extract::Path,
-    response::{Html, IntoResponse},
-    routing::{get, post},
-    Router, http::StatusCode, Form,
+    extract::{Path, State},
+    http::{
+        header::{AUTHORIZATION, VARY},
+        HeaderMap, StatusCode,
+    },
+    middleware,
+    response::{Html, IntoResponse, Response},
+    routing::{delete, get, post},
+    Form, Json, Router,
+};
+use futures_util::future::{join, ready, Either};
+use minijinja::context;
+use tokio::{net::TcpListener, select, signal, time};
+use tower_http::{
+    services::ServeDir,
+    trace::{DefaultOnResponse, TraceLayer},
 };
-use minijinja::render;
-use tokio::{net::TcpListener, select};
-use tower_http::services::ServeDir;
+use tracing::Level;
 
 use aoc2023::*;
 
+mod api;
+mod assets;
+mod cache;
+mod config;
+mod highlight;
+mod jobs;
+mod metrics;
+mod pages;
+mod results;
+mod trace;
+
+use config::Config;
+
+/// Shared by every handler
+#[derive(Clone)]
+struct AppState {
+    pool: Pool,
+    jobs: Arc<jobs::Jobs>,
+    cache: Arc<cache::Cache>,
+    metrics: Arc<metrics::Metrics>,
+    results: Arc<results::Results>,
+    admin_token: Option<Arc<str>>,
+}
+
+/// How long a solve from the page can take before it's answered with a progress bar instead
+const QUICK_SOLVE: Duration = Duration::from_millis(200);
+/// How often expired results are cleared out of the data dir
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+/// How long connections have to close after the solves are drained, before the server stops regardless
+const CLOSE_GRACE: Duration = Duration::from_secs(2);
+
 #[tokio::main]
-async fn main() {
-    // Very first thing is set up the shutdown
+async fn main() -> ExitCode {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    trace::init(&config);
+    pages::install_panic_hook();
+
+    // Next thing is set up the shutdown, which everything that has to stop with the server listens for
     let (sender, mut receiver) = tokio::sync::broadcast::channel::<()>(1);
 
     // start tailwind when in dev mode
@@ -36,7 +94,7 @@ async fn main() {
             .spawn()
         {
             Ok(mut tailwind) => {
-                println!("tailwind successfully started");
+                tracing::info!("tailwind started");
                 let mut stderr_reader = BufReader::new(tailwind.stderr.take().unwrap()).lines();
 
                 tokio::spawn(async move {
@@ -44,10 +102,12 @@ async fn main() {
                         res = tailwind.wait() => { match res {
                             Ok(_) => {}
                             Err(error) => {
-                                println!("tailwind crashed: {}", error);
+                                tracing::error!("tailwind crashed: {error}");
                             }
                         }},
-                        _exit = powershell.recv() => {}
+                        _exit = powershell.recv() => {
+                            let _ = tailwind.kill().await;
+                        }
                     }
                 });
 
@@ -56,7 +116,7 @@ async fn main() {
                         select! {
                             err = stderr_reader.next_line() => {
                                 if !matches!(err.as_ref().map(|op| op.as_ref().map(|s| s.as_str())), Ok(None) | Ok(Some(""))) {
-                                    println!("tailwind stderr: {:?}", err);
+                                    tracing::warn!("tailwind: {err:?}");
                                 }
                             },
                             _exit = readers.recv() => { break; }
@@ -64,164 +124,329 @@ async fn main() {
                     }
                 });
             }
-            Err(_) => {
-                println!("tailwind failed");
+            Err(error) => {
+                tracing::warn!("tailwind didn't start, so the CSS won't be rebuilt: {error}");
             }
         }
     }
 
-    ctrlc::set_handler(move || {
-        let _ = sender.send(());
-    })
-    .unwrap();
+    let results = match results::Results::open(&config.data_dir, config.retention) {
+        Ok(results) => Arc::new(results),
+        Err(error) => {
+            tracing::error!("could not open the results in {}: {error}", config.data_dir.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    tokio::spawn({
+        let results = results.clone();
+        async move {
+            let mut interval = time::interval(PRUNE_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let results = results.clone();
+                match tokio::task::spawn_blocking(move || results.prune()).await {
+                    Ok(Ok(0)) => {}
+                    Ok(Ok(pruned)) => tracing::info!("cleared out {pruned} expired results"),
+                    Ok(Err(error)) => tracing::warn!("could not clear out the expired results: {error}"),
+                    Err(_) => {}
+                }
+            }
+        }
+    });
 
+    let pool = Pool::new(config.solve_concurrency, config.solve_timeout);
+    let state = AppState {
+        pool: pool.clone(),
+        jobs: Default::default(),
+        cache: Arc::new(cache::Cache::new(config.cache_size, config.cache_ttl)),
+        metrics: Default::default(),
+        results,
+        admin_token: config.admin_token.map(Into::into),
+    };
     let router = Router::new()
         .route("/", get(home))
         .route("/day/:day", get(solve))
         .route("/day/:day/part1", post(solve_part1))
         .route("/day/:day/part2", post(solve_part2))
-        .nest_service("/static", ServeDir::new("static"));
+        .route("/day/:day/solve", post(solve_both))
+        .route("/day/:day/source", get(source))
+        .route("/r/:id", get(results::page))
+        .route("/admin/cache", delete(clear_cache))
+        .route("/jobs/:id/events", get(jobs::events))
+        .nest("/api/v1", api::router())
+        .route("/healthz", get(metrics::healthz))
+        .route("/readyz", get(metrics::readyz))
+        .route("/metrics", get(metrics::metrics))
+        .route_layer(middleware::from_fn(pages::catch_panic))
+        .route_layer(middleware::from_fn_with_state(state.clone(), metrics::track))
+        .fallback(pages::not_found);
+    let router = match &config.static_dir {
+        Some(dir) => router.nest_service("/static", ServeDir::new(dir)),
+        None => router.route("/static/*path", get(assets::serve)),
+    };
+    let router = router
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(trace::request_span)
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        .with_state(state);
 
-    let addr = if cfg!(debug_assertions) {
-        SocketAddr::from(([127, 0, 0, 1], 80))
-    } else {
-        SocketAddr::from(([0, 0, 0, 0], 80))
+    let listener = match TcpListener::bind(config.addr).await {
+        Ok(listener) => listener,
+        Err(error) => {
+            tracing::error!("{}", bind_error(config.addr, &error));
+            return ExitCode::FAILURE;
+        }
     };
-    let listener = TcpListener::bind(addr).await.unwrap();
-    println!("listening on {}", &addr);
-
-    select! {
-        _ = async {
-            axum::serve(listener, router)
-                .await
-                .unwrap();
-        } => {},
-        _ = receiver.recv() => {}
+    tracing::info!("listening on {}", config.addr);
+
+    // Stops accepting connections once the signal comes, and finishes once the open ones are answered
+    let mut server = tokio::spawn(
+        axum::serve(listener, router)
+            .with_graceful_shutdown(async move {
+                shutdown_signal().await;
+                let _ = sender.send(());
+            })
+            .into_future(),
+    );
+
+    // The signal comes first, since the server can be done by the time this looks
+    let server_done = select! {
+        biased;
+        _ = receiver.recv() => false,
+        result = &mut server => match result {
+            // It only stops by itself once the signal came and every connection closed
+            Ok(Ok(())) => true,
+            Ok(Err(error)) => {
+                tracing::error!("the server stopped: {error}");
+                return ExitCode::FAILURE;
+            }
+            // The panic hook logged it
+            Err(_) => return ExitCode::FAILURE,
+        },
+    };
+
+    tracing::info!("shutting down, giving running solves {:?} to finish", config.shutdown_grace);
+    let drained = pool.drain(config.shutdown_grace).await;
+    tracing::info!("{} solves finished and {} were cancelled", drained.finished, drained.cancelled);
+
+    // Cancelled solves answer quickly, but event streams and slow clients only get so long to be sent it
+    if !server_done && time::timeout(CLOSE_GRACE, server).await.is_err() {
+        tracing::warn!("closed the connections that were still open after {CLOSE_GRACE:?}");
     }
+    ExitCode::SUCCESS
+}
+
+/// Ctrl-C, or SIGTERM from whatever's running the server, like `docker stop`
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("SIGTERM can always be listened for");
+        select! {
+            _ = signal::ctrl_c() => {},
+            _ = terminate.recv() => {},
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = signal::ctrl_c().await;
 }
 
 async fn home() -> Html<String> {
-    let mut days: Vec<String> = fs::read_dir("src/solutions")
-        .unwrap()
-        .filter_map(|res| {
-            res.unwrap()
-                .file_name()
-                .to_string_lossy()
-                .strip_prefix("day")
-                .map(|day| day.replace(".rs", "").to_string())
-        })
-        .collect();
-    days.sort();
-    let days: Vec<String> = days.into_iter()
-        .filter_map(|day| if day.starts_with('0') { day.strip_prefix('0').map(|s| s.to_string()) } else { Some(day) })
+    let days: Vec<_> = DAYS.iter()
+        .map(|entry| context!(number => entry.number, title => entry.title, parts => entry.parts))
         .collect();
 
-    Html(layout!(
-        "../assets/layouts/root.html",
-        "../assets/layouts/app.html",
-        render!(
-            include_str!("../assets/templates/index.html"),
-            days => days
-        )
-    ))
+    Html(templates::render("templates/index.html", context!(days => days)))
 }
 
-async fn solve(Path(day): Path<u8>) -> impl IntoResponse {
-    if day > 25 {
-        (StatusCode::NOT_FOUND, Html(String::new()))
-    } else {
-        (
-            StatusCode::OK,
-            Html(layout!(
-                "../assets/layouts/root.html",
-                "../assets/layouts/app.html",
-                render!(
-                    include_str!("../assets/templates/solutions.html"),
-                    day => day
-                )
-            ))
-        )
+async fn solve(Path(day): Path<u8>) -> Response {
+    match aoc2023::day(day) {
+        None => pages::page(StatusCode::NOT_FOUND, &format!("There's no day {day}.")),
+        Some(entry) => Html(templates::render(
+            "templates/solutions.html",
+            context!(
+                day => entry.number,
+                title => entry.title,
+                parts => entry.parts,
+                examples => entry.examples.iter().map(|example| example.input).collect::<Vec<_>>()
+            ),
+        ))
+        .into_response(),
     }
 }
 
-async fn get_part1<D: Day>(input: String) -> String {
-    D::part1(input).await
+/// The day's module, highlighted. HTMX only gets the code, for the panel on the day page.
+async fn source(Path(day): Path<u8>, headers: HeaderMap) -> Response {
+    let Some(entry) = aoc2023::day(day) else {
+        return pages::page(StatusCode::NOT_FOUND, &format!("There's no day {day}."));
+    };
+    let template = if headers.contains_key("hx-request") {
+        "templates/source_code.html"
+    } else {
+        "templates/source.html"
+    };
+    let page = templates::render(
+        template,
+        context!(day => entry.number, title => entry.title, lines => highlight::highlight(entry.source)),
+    );
+    ([(VARY, "hx-request")], Html(page)).into_response()
 }
 
-async fn get_part2<D: Day>(input: String) -> String {
-    D::part2(input).await
+async fn solve_part1(
+    State(state): State<AppState>,
+    Path(day): Path<u8>,
+    headers: HeaderMap,
+    Form(input): Form<PartInput>,
+) -> impl IntoResponse {
+    solve_part(&state, day, 1, &headers, input.input).await
 }
 
-async fn solve_part1(Path(day): Path<u8>, Form(input): Form<PartInput>) -> impl IntoResponse {
-    // I am supremely disappointed that I didn't find a better way to do this.
-    // At least this way implementing the Day trait is enforced.
-    // I am still somewhat tempted to try making a function-like proc-macro and add
-    // two routes for each day rather than 25 days for 2 routes.
-    let output = match day {
-        1 =>  get_part1::<day01::Day01>(input.input).await,
-        2 =>  get_part1::<day02::Day02>(input.input).await,
-        3 =>  get_part1::<day03::Day03>(input.input).await,
-        4 =>  get_part1::<day04::Day04>(input.input).await,
-        5 =>  get_part1::<day05::Day05>(input.input).await,
-        6 =>  get_part1::<day06::Day06>(input.input).await,
-        7 =>  get_part1::<day07::Day07>(input.input).await,
-        8 =>  get_part1::<day08::Day08>(input.input).await,
-        9 =>  get_part1::<day09::Day09>(input.input).await,
-        10 =>  get_part1::<day10::Day10>(input.input).await,
-        11 =>  get_part1::<day11::Day11>(input.input).await,
-        12 =>  get_part1::<day12::Day12>(input.input).await,
-        13 =>  get_part1::<day13::Day13>(input.input).await,
-        14 =>  get_part1::<day14::Day14>(input.input).await,
-        15 =>  get_part1::<day15::Day15>(input.input).await,
-        16 =>  get_part1::<day16::Day16>(input.input).await,
-        17 =>  get_part1::<day17::Day17>(input.input).await,
-        18 =>  get_part1::<day18::Day18>(input.input).await,
-        19 =>  get_part1::<day19::Day19>(input.input).await,
-        20 =>  get_part1::<day20::Day20>(input.input).await,
-        21 =>  get_part1::<day21::Day21>(input.input).await,
-        22 =>  get_part1::<day22::Day22>(input.input).await,
-        23 =>  get_part1::<day23::Day23>(input.input).await,
-        24 =>  get_part1::<day24::Day24>(input.input).await,
-        25 =>  get_part1::<day25::Day25>(input.input).await,
-        _ => { return (StatusCode::NOT_FOUND, Html(format!("Day {day} not found"))); }
+async fn solve_part2(
+    State(state): State<AppState>,
+    Path(day): Path<u8>,
+    headers: HeaderMap,
+    Form(input): Form<PartInput>,
+) -> impl IntoResponse {
+    solve_part(&state, day, 2, &headers, input.input).await
+}
+
+async fn solve_part(state: &AppState, day: u8, part: u8, headers: &HeaderMap, input: String) -> Response {
+    let Some((entry, solver)) = aoc2023::day(day).and_then(|entry| Some((entry, entry.solver(part)?))) else {
+        return pages::no_such_day(day, headers);
+    };
+
+    let input = Input::from(input);
+    let key = state.cache.key(day, part, &input);
+    if let Some(cached) = state.cache.get(&key) {
+        return (cache::header(true), cached.fragment()).into_response();
+    }
+
+    let solve = trace::Solve::new(&state.metrics, day, part, &input);
+    let start = Instant::now();
+    let fragment = match state.pool.parse(entry.parse, input).await {
+        Ok(parsed) => job_fragment(&state.start_job(solve, solver, parsed, key, start.elapsed())).await,
+        Err(error) => {
+            let result = Err(error);
+            solve.finish(None, &result);
+            solution_fragment(result, None, false, None)
+        }
+    };
+    (cache::header(false), fragment).into_response()
+}
+
+/// Parses the input once and solves both parts side by side. The answer to part 1 goes into `#solution1`,
+/// which the request targets, and part 2's is swapped into `#solution2` out of band.
+/// Parts that were solved for this input before come from the cache, and if both do, nothing is parsed.
+async fn solve_both(
+    State(state): State<AppState>,
+    Path(day): Path<u8>,
+    headers: HeaderMap,
+    Form(input): Form<PartInput>,
+) -> Response {
+    let Some(entry) = aoc2023::day(day) else {
+        return pages::no_such_day(day, &headers);
+    };
+
+    let input = Input::from(input.input);
+    let keys = [1, 2].map(|part| state.cache.key(day, part, &input));
+    let [cached1, cached2] = keys.map(|key| state.cache.get(&key));
+    let hit = cached1.is_some() && cached2.is_some();
+
+    let (part1, part2) = if let (Some(cached1), Some(cached2)) = (&cached1, &cached2) {
+        (cached1.clone().fragment(), cached2.clone().fragment())
+    } else {
+        let solves = [1, 2].map(|part| trace::Solve::new(&state.metrics, day, part, &input));
+        let start = Instant::now();
+        match state.pool.parse(entry.parse, input).await {
+            Ok(parsed) => {
+                let parse = start.elapsed();
+                let [solve1, solve2] = solves;
+                let [fragment1, fragment2] = [
+                    (cached1, solve1, entry.part1, keys[0]),
+                    (cached2, solve2, entry.part2, keys[1]),
+                ]
+                .map(|(cached, solve, solver, key)| match cached {
+                    Some(cached) => Either::Left(ready(cached.fragment())),
+                    None => {
+                        let job = state.start_job(solve, solver, parsed.clone(), key, parse);
+                        Either::Right(async move { job_fragment(&job).await })
+                    }
+                });
+                join(fragment1, fragment2).await
+            }
+            Err(error) => {
+                let result = Err(error);
+                for solve in &solves {
+                    solve.finish(None, &result);
+                }
+                (solution_fragment(result.clone(), None, false, None), solution_fragment(result, None, false, None))
+            }
+        }
     };
+    let fragment = templates::render("templates/both.html", context!(part1 => part1.0, part2 => part2.0));
+    (cache::header(hit), Html(fragment)).into_response()
+}
 
-    (StatusCode::OK, Html(output))
+/// The job's answer if it comes quickly, otherwise a progress bar that follows it
+async fn job_fragment(job: &jobs::Job) -> Html<String> {
+    match job.wait(QUICK_SOLVE).await {
+        Some(solved) => solved.fragment(),
+        None => Html(templates::render("templates/job.html", context!(id => job.id))),
+    }
 }
 
-async fn solve_part2(Path(day): Path<u8>, Form(input): Form<PartInput>) -> impl IntoResponse {
-    // I am supremely disappointed that I didn't find a better way to do this.
-    // At least this way implementing the Day trait is enforced.
-    // I am still somewhat tempted to try making a function-like proc-macro and add
-    // two routes for each day rather than 25 days for 2 routes.
-    let output = match day {
-        1 =>  get_part2::<day01::Day01>(input.input).await,
-        2 =>  get_part2::<day02::Day02>(input.input).await,
-        3 =>  get_part2::<day03::Day03>(input.input).await,
-        4 =>  get_part2::<day04::Day04>(input.input).await,
-        5 =>  get_part2::<day05::Day05>(input.input).await,
-        6 =>  get_part2::<day06::Day06>(input.input).await,
-        7 =>  get_part2::<day07::Day07>(input.input).await,
-        8 =>  get_part2::<day08::Day08>(input.input).await,
-        9 =>  get_part2::<day09::Day09>(input.input).await,
-        10 =>  get_part2::<day10::Day10>(input.input).await,
-        11 =>  get_part2::<day11::Day11>(input.input).await,
-        12 =>  get_part2::<day12::Day12>(input.input).await,
-        13 =>  get_part2::<day13::Day13>(input.input).await,
-        14 =>  get_part2::<day14::Day14>(input.input).await,
-        15 =>  get_part2::<day15::Day15>(input.input).await,
-        16 =>  get_part2::<day16::Day16>(input.input).await,
-        17 =>  get_part2::<day17::Day17>(input.input).await,
-        18 =>  get_part2::<day18::Day18>(input.input).await,
-        19 =>  get_part2::<day19::Day19>(input.input).await,
-        20 =>  get_part2::<day20::Day20>(input.input).await,
-        21 =>  get_part2::<day21::Day21>(input.input).await,
-        22 =>  get_part2::<day22::Day22>(input.input).await,
-        23 =>  get_part2::<day23::Day23>(input.input).await,
-        24 =>  get_part2::<day24::Day24>(input.input).await,
-        25 =>  get_part2::<day25::Day25>(input.input).await,
-        _ => { return (StatusCode::NOT_FOUND, Html(format!("Day {day} not found"))); }
+impl AppState {
+    /// Starts solving one part in the background under its span. An answer is stored to be shared,
+    /// and cached under `key`.
+    fn start_job(
+        &self,
+        solve: trace::Solve,
+        solver: Solver,
+        parsed: ParsedInput,
+        key: cache::Key,
+        parse: Duration,
+    ) -> Arc<jobs::Job> {
+        let (cache, results) = (self.cache.clone(), self.results.clone());
+        let _span = solve.span.clone().entered();
+        self.jobs.start(&self.pool, solver, parsed, move |solved| {
+            solve.finish(Some(solved.elapsed), &solved.result);
+            if let Ok(answer) = &solved.result {
+                solved.permalink = Some(results.record(solve.day, solve.part, solve.input, answer, parse, solved.elapsed));
+                let cached = cache::Cached {
+                    answer: answer.clone(),
+                    parse,
+                    solve: solved.elapsed,
+                    permalink: solved.permalink.clone(),
+                };
+                cache.insert(key, cached);
+            }
+        })
+    }
+}
+
+/// Empties the answer cache. The request needs the admin token as a bearer token,
+/// and without one configured there's no such endpoint.
+async fn clear_cache(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let Some(token) = &state.admin_token else {
+        return pages::not_found().await;
     };
+    let given = headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok());
+    if given != Some(format!("Bearer {token}").as_str()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    Json(serde_json::json!({ "cleared": state.cache.clear() })).into_response()
+}
 
-    (StatusCode::OK, Html(output))
+/// Says why listening on `addr` failed, with a hint for the usual reasons
+fn bind_error(addr: SocketAddr, error: &io::Error) -> String {
+    let hint = match error.kind() {
+        io::ErrorKind::PermissionDenied => " (ports below 1024 usually need root, try a higher --port)",
+        io::ErrorKind::AddrInUse => " (something else is listening there, pick another --port)",
+        io::ErrorKind::AddrNotAvailable => " (that address doesn't belong to this machine, check --bind)",
+        _ => "",
+    };
+    format!("could not listen on {addr}: {error}{hint}")
 }
@@ -0,0 +1,166 @@
+//! Counters served at `/metrics` in Prometheus' text format, plus the health checks load balancers poll
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::Mutex,
+    time::Duration,
+};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use aoc2023::{Answer, SolveError};
+
+use crate::AppState;
+
+/// Upper bounds of the solve duration buckets, in seconds
+const BUCKETS: [f64; 10] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0, 10.0];
+
+#[derive(Default)]
+pub struct Metrics {
+    inner: Mutex<Inner>,
+}
+
+/// Kept in `BTreeMap`s so the output comes out in the same order every time
+#[derive(Default)]
+struct Inner {
+    /// By route and status
+    requests: BTreeMap<(String, u16), u64>,
+    /// By day and part
+    solves: BTreeMap<(u8, u8), Histogram>,
+    /// By day, part and `SolveError::kind`
+    errors: BTreeMap<(u8, u8, &'static str), u64>,
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// How many observations were at most each of `BUCKETS`
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, _) in self.buckets.iter_mut().zip(BUCKETS).filter(|&(_, le)| value <= le) {
+            *bucket += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+impl Metrics {
+    pub fn request(&self, route: String, status: StatusCode) {
+        *self.inner.lock().unwrap().requests.entry((route, status.as_u16())).or_default() += 1;
+    }
+
+    /// Records a solve that ran for `elapsed`, and its error if it failed
+    pub fn solved(&self, day: u8, part: u8, elapsed: Duration, result: &Result<Answer, SolveError>) {
+        self.inner.lock().unwrap().solves.entry((day, part)).or_default().observe(elapsed.as_secs_f64());
+        if let Err(error) = result {
+            self.failed(day, part, error);
+        }
+    }
+
+    /// Records an error from a part that never got to be solved, because its input didn't parse
+    pub fn failed(&self, day: u8, part: u8, error: &SolveError) {
+        *self.inner.lock().unwrap().errors.entry((day, part, error.kind())).or_default() += 1;
+    }
+
+    fn render(&self, state: &AppState) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut out = String::new();
+
+        header(&mut out, "aoc_http_requests_total", "counter", "Requests answered, by route and status");
+        for ((route, status), count) in &inner.requests {
+            let _ = writeln!(out, "aoc_http_requests_total{{route=\"{}\",status=\"{status}\"}} {count}", escape(route));
+        }
+
+        header(&mut out, "aoc_solve_duration_seconds", "histogram", "How long solves took, by day and part");
+        for ((day, part), histogram) in &inner.solves {
+            let labels = format!("day=\"{day}\",part=\"{part}\"");
+            for (le, count) in BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(out, "aoc_solve_duration_seconds_bucket{{{labels},le=\"{le}\"}} {count}");
+            }
+            let _ = writeln!(out, "aoc_solve_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}", histogram.count);
+            let _ = writeln!(out, "aoc_solve_duration_seconds_sum{{{labels}}} {}", histogram.sum);
+            let _ = writeln!(out, "aoc_solve_duration_seconds_count{{{labels}}} {}", histogram.count);
+        }
+
+        header(&mut out, "aoc_solve_errors_total", "counter", "Parts that didn't get an answer, by day, part and kind of error");
+        for ((day, part, kind), count) in &inner.errors {
+            let _ = writeln!(out, "aoc_solve_errors_total{{day=\"{day}\",part=\"{part}\",kind=\"{kind}\"}} {count}");
+        }
+
+        header(&mut out, "aoc_solves_active", "gauge", "Parses and solves queued or running");
+        let _ = writeln!(out, "aoc_solves_active {}", state.pool.active());
+
+        let stats = state.cache.stats();
+        header(&mut out, "aoc_cache_lookups_total", "counter", "Answer cache lookups, by whether they found one");
+        let _ = writeln!(out, "aoc_cache_lookups_total{{result=\"hit\"}} {}", stats.hits);
+        let _ = writeln!(out, "aoc_cache_lookups_total{{result=\"miss\"}} {}", stats.misses);
+        header(&mut out, "aoc_cache_entries", "gauge", "Answers in the cache");
+        let _ = writeln!(out, "aoc_cache_entries {}", stats.entries);
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+/// Escapes a label value the way the text format wants
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Counts every request that matched a route under that route's pattern, so `/day/1` and `/day/2`
+/// are both `/day/:day`. Static files and paths that match nothing aren't routes, so they aren't counted.
+pub async fn track(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let route = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_owned());
+    let response = next.run(request).await;
+    if let Some(route) = route {
+        state.metrics.request(route, response.status());
+    }
+    response
+}
+
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], state.metrics.render(&state)).into_response()
+}
+
+/// Up as long as the process is answering at all
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Up until the server starts shutting down, so the load balancer stops sending it new work
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, &'static str) {
+    if state.pool.is_draining() {
+        (StatusCode::SERVICE_UNAVAILABLE, "shutting down")
+    } else {
+        (StatusCode::OK, "ok")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::default();
+        histogram.observe(0.003);
+        histogram.observe(0.3);
+        histogram.observe(60.0);
+        assert_eq!(histogram.buckets, [0, 1, 1, 1, 1, 1, 2, 2, 2, 2]);
+        assert_eq!(histogram.count, 3);
+    }
+}
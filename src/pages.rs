@@ -0,0 +1,58 @@
+//! What's sent when something goes wrong outside a solver: the 404 and 500 pages, and panics in handlers
+//! turned into the 500 page, or into an error fragment when HTMX asked (solver panics are `SolveError`s)
+
+use std::{backtrace::Backtrace, panic::AssertUnwindSafe};
+
+use axum::{
+    extract::Request,
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{Html, IntoResponse, Response},
+};
+use futures_util::FutureExt;
+use minijinja::context;
+
+use aoc2023::templates;
+
+/// Logs panics with a backtrace, under whatever span they happened in, which for a solver says its day and part
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        tracing::error!("{info}\n{}", Backtrace::force_capture());
+    }));
+}
+
+/// A page with the app's layout saying what went wrong
+pub fn page(status: StatusCode, message: &str) -> Response {
+    let title = format!("{} {}", status.as_u16(), status.canonical_reason().unwrap_or_default());
+    let page = templates::render("templates/status.html", context!(status => title, message => message));
+    (status, Html(page)).into_response()
+}
+
+/// The error fragment, for HTMX to swap in where the answer would have gone
+pub fn fragment(status: StatusCode, message: &str) -> Response {
+    let fragment = templates::render("templates/error.html", context!(message => message));
+    (status, Html(fragment)).into_response()
+}
+
+pub async fn not_found() -> Response {
+    page(StatusCode::NOT_FOUND, "There's nothing here.")
+}
+
+/// A 404 for a day that isn't in the calendar, as a fragment when HTMX asked and a page otherwise
+pub fn no_such_day(day: u8, headers: &HeaderMap) -> Response {
+    if headers.contains_key("hx-request") {
+        fragment(StatusCode::NOT_FOUND, &format!("there's no day {day}"))
+    } else {
+        page(StatusCode::NOT_FOUND, &format!("There's no day {day}."))
+    }
+}
+
+/// Answers a request whose handler panicked, instead of dropping the connection
+pub async fn catch_panic(request: Request, next: Next) -> Response {
+    let htmx = request.headers().contains_key("hx-request");
+    match AssertUnwindSafe(next.run(request)).catch_unwind().await {
+        Ok(response) => response,
+        Err(_) if htmx => fragment(StatusCode::INTERNAL_SERVER_ERROR, "something went wrong on our side"),
+        Err(_) => page(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong on our side."),
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{Notify, Semaphore},
+    task, time,
+};
+
+use crate::{Answer, Context, Input, ParsedInput, Parser, SolveError, Solver};
+
+/// Runs solvers on tokio's blocking threads, so a slow one can't hold up the async workers,
+/// with a limit on how many run at once and for how long.
+#[derive(Clone)]
+pub struct Pool {
+    permits: Arc<Semaphore>,
+    timeout: Duration,
+    active: Arc<Active>,
+}
+
+/// Everything queued or running, so it can be waited for or cancelled when the server stops
+#[derive(Default)]
+struct Active {
+    next: AtomicU64,
+    contexts: Mutex<HashMap<u64, Context>>,
+    /// Told whenever something finishes
+    finished: Notify,
+    /// Set once draining starts, after which nothing new is taken on
+    closed: AtomicBool,
+}
+
+/// Takes its work off `Active` when dropped, which is once the work is done or given up on before it started
+struct Tracked {
+    active: Arc<Active>,
+    id: u64,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.active.contexts.lock().unwrap().remove(&self.id);
+        self.active.finished.notify_waiters();
+    }
+}
+
+/// How draining went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Drained {
+    /// Work that finished within the grace period
+    pub finished: usize,
+    /// Work that was still going at the end of it and was cancelled
+    pub cancelled: usize,
+}
+
+impl Pool {
+    pub fn new(concurrency: usize, timeout: Duration) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(concurrency.max(1))),
+            timeout,
+            active: Default::default(),
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// How many parses and solves are queued or running
+    pub fn active(&self) -> usize {
+        self.active.contexts.lock().unwrap().len()
+    }
+
+    /// Whether `drain` has been called, so nothing new is being taken on
+    pub fn is_draining(&self) -> bool {
+        self.active.closed.load(Ordering::SeqCst)
+    }
+
+    /// Parses once a slot is free, within the same timeout as a solve (though parsing can't be cancelled early).
+    pub async fn parse(&self, parser: Parser, input: Input) -> Result<ParsedInput, SolveError> {
+        self.run(Context::new(), move |_cx| parser(&input)).await
+    }
+
+    /// Solves once a slot is free. If that (waiting included) takes longer than the timeout, the solver is
+    /// cancelled and this returns `SolveError::Timeout` right away; the slot stays taken until the solver
+    /// actually stops. A solver that panics gives `SolveError::Panicked`.
+    pub async fn solve(&self, solver: Solver, parsed: ParsedInput) -> Result<Answer, SolveError> {
+        self.solve_with(solver, parsed, Context::new()).await
+    }
+
+    /// Like `solve`, with a context the caller keeps a clone of, to follow the progress or cancel early.
+    pub async fn solve_with(&self, solver: Solver, parsed: ParsedInput, cx: Context) -> Result<Answer, SolveError> {
+        self.run(cx, move |cx| solver(&parsed, cx)).await
+    }
+
+    async fn run<T: Send + 'static>(
+        &self,
+        cx: Context,
+        work: impl FnOnce(&Context) -> Result<T, SolveError> + Send + 'static,
+    ) -> Result<T, SolveError> {
+        let tracked = self.track(&cx)?;
+        let run = {
+            let cx = cx.clone();
+            let permits = self.permits.clone();
+            async move {
+                let permit = permits.acquire_owned().await.expect("the semaphore is never closed");
+                // So what the work logs ends up under whatever span it was started in
+                let span = tracing::Span::current();
+                task::spawn_blocking(move || {
+                    let _permit = permit;
+                    let _tracked = tracked;
+                    span.in_scope(|| work(&cx))
+                })
+                .await
+            }
+        };
+
+        match time::timeout(self.timeout, run).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(error)) => Err(SolveError::Panicked(crate::panic_message(&*error.into_panic()))),
+            Err(_) => {
+                cx.cancel();
+                Err(SolveError::Timeout { limit: self.timeout })
+            }
+        }
+    }
+
+    /// Stops taking on work, so anything new fails with `SolveError::Cancelled`, then waits up to `grace`
+    /// for what's queued or running to finish and cancels whatever hasn't by then.
+    pub async fn drain(&self, grace: Duration) -> Drained {
+        self.active.closed.store(true, Ordering::SeqCst);
+        let before = self.active.contexts.lock().unwrap().len();
+        let _ = time::timeout(grace, async {
+            loop {
+                let finished = self.active.finished.notified();
+                if self.active.contexts.lock().unwrap().is_empty() {
+                    break;
+                }
+                finished.await;
+            }
+        })
+        .await;
+
+        let contexts = self.active.contexts.lock().unwrap();
+        for cx in contexts.values() {
+            cx.cancel();
+        }
+        Drained { finished: before.saturating_sub(contexts.len()), cancelled: contexts.len() }
+    }
+
+    fn track(&self, cx: &Context) -> Result<Tracked, SolveError> {
+        let mut contexts = self.active.contexts.lock().unwrap();
+        if self.active.closed.load(Ordering::SeqCst) {
+            return Err(SolveError::Cancelled);
+        }
+        let id = self.active.next.fetch_add(1, Ordering::Relaxed);
+        contexts.insert(id, cx.clone());
+        Ok(Tracked { active: self.active.clone(), id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forever(_parsed: &ParsedInput, cx: &Context) -> Result<Answer, SolveError> {
+        loop {
+            cx.check()?;
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let pool = Pool::new(1, Duration::from_millis(20));
+        let parsed = pool.parse(crate::day(25).unwrap().parse, Input::new("")).await.unwrap();
+        let result = pool.solve(forever, parsed.clone()).await;
+        assert_eq!(result, Err(SolveError::Timeout { limit: Duration::from_millis(20) }));
+        // The slot frees up once the solver notices it was cancelled
+        let result = pool.solve(|_, _| Ok(1.into()), parsed.clone()).await;
+        assert_eq!(result, Ok(Answer::Signed(1)));
+    }
+
+    #[tokio::test]
+    async fn test_panic() {
+        let pool = Pool::new(1, Duration::from_secs(60));
+        let parsed = pool.parse(crate::day(25).unwrap().parse, Input::new("")).await.unwrap();
+        let result = pool.solve(|_, _| panic!("out of {}", "cheese"), parsed.clone()).await;
+        assert_eq!(result, Err(SolveError::Panicked("out of cheese".into())));
+        let result = pool.solve(|_, _| Ok(1.into()), parsed).await;
+        assert_eq!(result, Ok(Answer::Signed(1)));
+    }
+
+    #[tokio::test]
+    async fn test_drain() {
+        let pool = Pool::new(2, Duration::from_secs(60));
+        let parsed = pool.parse(crate::day(25).unwrap().parse, Input::new("")).await.unwrap();
+        let slow = tokio::spawn({
+            let (pool, parsed) = (pool.clone(), parsed.clone());
+            async move { pool.solve(forever, parsed).await }
+        });
+        let quick = tokio::spawn({
+            let (pool, parsed) = (pool.clone(), parsed.clone());
+            async move {
+                pool.solve(|_, _| {
+                    std::thread::sleep(Duration::from_millis(10));
+                    Ok(1.into())
+                }, parsed).await
+            }
+        });
+        time::sleep(Duration::from_millis(5)).await;
+
+        let drained = pool.drain(Duration::from_millis(50)).await;
+        assert_eq!(drained, Drained { finished: 1, cancelled: 1 });
+        assert_eq!(quick.await.unwrap(), Ok(Answer::Signed(1)));
+        assert_eq!(slow.await.unwrap(), Err(SolveError::Cancelled));
+        assert_eq!(pool.solve(|_, _| Ok(1.into()), parsed).await, Err(SolveError::Cancelled));
+    }
+}
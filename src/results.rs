@@ -0,0 +1,323 @@
+//! Solves kept on disk so they can be shared and come back to at `/r/:id`. Each one is appended to
+//! `results.jsonl` in the data dir as it happens, and the file is rewritten without the expired ones now and then.
+//! Only a fingerprint of the input is kept, not the input itself. The file is only ever written by a thread
+//! of its own, so saving a result doesn't hold up the request it came from.
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fs::{self, File, OpenOptions},
+    hash::BuildHasher,
+    io::{self, Write as _},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use minijinja::context;
+use serde::{Deserialize, Serialize};
+
+use aoc2023::{templates, Answer, Input};
+
+use crate::{pages, AppState};
+
+/// How long the ids are, in letters and digits
+const ID_LEN: usize = 8;
+const ID_CHARS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub struct Results {
+    retention: Duration,
+    /// Keys the ids, so they can't be guessed from one another
+    ids: RandomState,
+    count: AtomicU64,
+    /// Held while a write is sent to the writer too, so the file gets them in the order the records changed
+    records: Mutex<HashMap<String, Record>>,
+    writes: mpsc::Sender<Write>,
+    writer: Option<thread::JoinHandle<()>>,
+}
+
+/// What the writer thread is asked to do to the file
+enum Write {
+    Append(String),
+    /// Replaces the whole file with this, saying whether that worked
+    Rewrite(String, mpsc::Sender<io::Result<()>>),
+    Stop,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub id: String,
+    pub day: u8,
+    pub part: u8,
+    /// `fingerprint` of the input
+    pub input: String,
+    pub answer: String,
+    pub parse_ms: f64,
+    pub solve_ms: f64,
+    /// Seconds since the Unix epoch
+    pub solved_at: u64,
+}
+
+/// FNV-1a of the normalized input, which unlike the cache's hasher is the same from one run of the server to the next
+pub fn fingerprint(input: &Input) -> u64 {
+    input
+        .as_str()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+impl Results {
+    /// Reads what's kept in `dir`, creating it if need be, and drops whatever has expired
+    pub fn open(dir: &Path, retention: Duration) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join("results.jsonl");
+
+        let mut records = HashMap::new();
+        let mut unreadable = 0;
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                    match serde_json::from_str::<Record>(line) {
+                        Ok(record) => {
+                            records.insert(record.id.clone(), record);
+                        }
+                        Err(_) => unreadable += 1,
+                    }
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+        if unreadable > 0 {
+            tracing::warn!("skipped {unreadable} unreadable lines in {}", path.display());
+        }
+
+        let log = OpenOptions::new().create(true).append(true).open(&path)?;
+        let (writes, received) = mpsc::channel();
+        let writer = thread::Builder::new()
+            .name("results".into())
+            .spawn(move || write(&path, log, received))?;
+        let results = Self {
+            retention,
+            ids: RandomState::new(),
+            count: AtomicU64::new(0),
+            records: Mutex::new(records),
+            writes,
+            writer: Some(writer),
+        };
+        results.prune()?;
+        Ok(results)
+    }
+
+    /// Keeps an answer, returning its id. It's saved in the background, and a failure to is only logged,
+    /// in which case the result is gone once the server restarts.
+    pub fn record(&self, day: u8, part: u8, input: u64, answer: &Answer, parse: Duration, solve: Duration) -> String {
+        let mut records = self.records.lock().unwrap();
+        let id = loop {
+            let id = self.new_id();
+            if !records.contains_key(&id) {
+                break id;
+            }
+        };
+        let record = Record {
+            id: id.clone(),
+            day,
+            part,
+            input: format!("{input:016x}"),
+            answer: answer.to_string(),
+            parse_ms: parse.as_secs_f64() * 1000.0,
+            solve_ms: solve.as_secs_f64() * 1000.0,
+            solved_at: now(),
+        };
+        let line = serde_json::to_string(&record).expect("records always serialize");
+        let _ = self.writes.send(Write::Append(line));
+        records.insert(id.clone(), record);
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<Record> {
+        let records = self.records.lock().unwrap();
+        records.get(id).filter(|record| !self.expired(record)).cloned()
+    }
+
+    /// When a record stops being served, in seconds since the Unix epoch
+    pub fn expires_at(&self, record: &Record) -> u64 {
+        record.solved_at.saturating_add(self.retention.as_secs())
+    }
+
+    /// Drops expired records, rewriting the file without them if there were any. Returns how many went.
+    /// This waits for the file to be written, so it's for blocking threads.
+    pub fn prune(&self) -> io::Result<usize> {
+        let (dropped, done) = {
+            let mut records = self.records.lock().unwrap();
+            let before = records.len();
+            records.retain(|_, record| !self.expired(record));
+            let dropped = before - records.len();
+            if dropped == 0 {
+                return Ok(0);
+            }
+
+            let mut kept: Vec<_> = records.values().collect();
+            kept.sort_by_key(|record| (record.solved_at, &record.id));
+            let mut text = String::new();
+            for record in kept {
+                text += &serde_json::to_string(record).expect("records always serialize");
+                text.push('\n');
+            }
+            let (sender, done) = mpsc::channel();
+            let _ = self.writes.send(Write::Rewrite(text, sender));
+            (dropped, done)
+        };
+        let written = done.recv().unwrap_or_else(|_| Err(io::Error::other("the results writer stopped")));
+        written.map(|()| dropped)
+    }
+
+    fn expired(&self, record: &Record) -> bool {
+        self.expires_at(record) <= now()
+    }
+
+    fn new_id(&self) -> String {
+        let mut hash = self.ids.hash_one(self.count.fetch_add(1, Ordering::Relaxed));
+        (0..ID_LEN)
+            .map(|_| {
+                let c = ID_CHARS[(hash % ID_CHARS.len() as u64) as usize];
+                hash /= ID_CHARS.len() as u64;
+                c as char
+            })
+            .collect()
+    }
+}
+
+/// Lets the writer finish what it was sent before the server exits
+impl Drop for Results {
+    fn drop(&mut self) {
+        let _ = self.writes.send(Write::Stop);
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// The writer thread, which has the file to itself
+fn write(path: &Path, mut log: File, writes: mpsc::Receiver<Write>) {
+    for write in writes {
+        match write {
+            Write::Append(line) => {
+                if let Err(error) = writeln!(log, "{line}") {
+                    tracing::warn!("could not save a result to {}: {error}", path.display());
+                }
+            }
+            Write::Rewrite(text, done) => {
+                let rewritten = rewrite(path, &text).map(|rewritten| log = rewritten);
+                let _ = done.send(rewritten);
+            }
+            Write::Stop => break,
+        }
+    }
+}
+
+/// Replaces the file with `text`, returning it opened for appending again
+fn rewrite(path: &Path, text: &str) -> io::Result<File> {
+    // Written aside and moved into place, so a crash halfway leaves the old file whole
+    let temporary = path.with_extension("jsonl.tmp");
+    fs::write(&temporary, text)?;
+    fs::rename(&temporary, path)?;
+    OpenOptions::new().append(true).open(path)
+}
+
+/// The read-only page a result is shared as
+pub async fn page(State(state): State<AppState>, UrlPath(id): UrlPath<String>) -> Response {
+    let Some(record) = state.results.get(&id) else {
+        return pages::page(StatusCode::NOT_FOUND, "This result doesn't exist, or it's expired.");
+    };
+    let page = templates::render(
+        "templates/result.html",
+        context!(
+            day => record.day,
+            title => aoc2023::day(record.day).map(|entry| entry.title),
+            part => record.part,
+            answer => record.answer,
+            input => record.input,
+            parse_ms => format!("{:.3}", record.parse_ms),
+            solve_ms => format!("{:.3}", record.solve_ms),
+            solved_at => format_time(record.solved_at),
+            expires_at => format_time(state.results.expires_at(&record)),
+        ),
+    );
+    Html(page).into_response()
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+/// Formats seconds since the Unix epoch like `2023-12-01 05:00 UTC`
+pub fn format_time(secs: u64) -> String {
+    // Howard Hinnant's days-to-civil, counting from 0000-03-01 so leap days come last
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let minutes = secs % 86_400 / 60;
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02} UTC", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc2023-results-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_kept_across_restarts() {
+        let dir = dir("restart");
+        let results = Results::open(&dir, Duration::from_secs(3600)).unwrap();
+        let id = results.record(1, 2, 0xabc, &Answer::Signed(42), Duration::ZERO, Duration::from_millis(3));
+        assert_eq!(id.len(), ID_LEN);
+        drop(results);
+
+        let results = Results::open(&dir, Duration::from_secs(3600)).unwrap();
+        let record = results.get(&id).unwrap();
+        assert_eq!((record.day, record.part, record.answer.as_str()), (1, 2, "42"));
+        assert_eq!(record.input, "0000000000000abc");
+        assert!(results.get("nope").is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expiry() {
+        let dir = dir("expiry");
+        let results = Results::open(&dir, Duration::ZERO).unwrap();
+        let id = results.record(1, 1, 0, &Answer::Signed(1), Duration::ZERO, Duration::ZERO);
+        assert!(results.get(&id).is_none());
+        assert_eq!(results.prune().unwrap(), 1);
+        assert_eq!(fs::read_to_string(dir.join("results.jsonl")).unwrap(), "");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_time(1_701_406_800), "2023-12-01 05:00 UTC");
+        assert_eq!(format_time(1_709_164_800), "2024-02-29 00:00 UTC");
+    }
+}
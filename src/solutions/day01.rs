@@ -1,16 +1,45 @@
-use crate::{lines, Day};
+use crate::{Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day01;
 
 impl Day for Day01 {
-    async fn part1(input: String) -> String {
-        let lines = lines(&input);
-        part1(&lines).to_string()
+    const EXAMPLES: &'static [Example] = &[
+        Example {
+            input: "1abc2
+pqr3stu8vwx
+a1b2c3d4e5f
+treb7uchet
+",
+            part1: Some("142"),
+            part2: None,
+        },
+        Example {
+            input: "two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqrstsixteen
+",
+            part1: None,
+            part2: Some("281"),
+        },
+    ];
+
+    /// Both parts look for digits in their own way, so there's nothing to do up front
+    type Parsed = Input;
+
+    fn parse(input: &Input) -> Result<Input, SolveError> {
+        Ok(input.clone())
     }
 
-    async fn part2(input: String) -> String {
-        let lines = lines(&input);
-        part2(&lines).to_string()
+    fn part1(input: &Input, _cx: &Context) -> Result<Answer, SolveError> {
+        Ok(part1(&input.lines()).into())
+    }
+
+    fn part2(input: &Input, _cx: &Context) -> Result<Answer, SolveError> {
+        Ok(part2(&input.lines()).into())
     }
 }
 
@@ -42,68 +71,37 @@ fn to_i32(b: u8) -> i32 {
 fn part2(input: &[&str]) -> i32 {
     input
         .iter()
-        .map(|line| parse_line(line, None))
+        .map(|line| parse_line(line))
         .map(|(first, last)| first * 10 + last)
         .sum()
 }
 
-fn parse_line(line: &str, digits: Option<(i32, i32)>) -> (i32, i32) {
-    if line.is_empty() {
-        digits.unwrap_or((0, 0))
-    } else {
-        let next = line.split_at(1).1;
-        if line.starts_with('0') || line.starts_with("zero") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((0, 0)), |(a, _)| Some((a, 0))),
-            )
-        } else if line.starts_with('1') || line.starts_with("one") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((1, 1)), |(a, _)| Some((a, 1))),
-            )
-        } else if line.starts_with('2') || line.starts_with("two") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((2, 2)), |(a, _)| Some((a, 2))),
-            )
-        } else if line.starts_with('3') || line.starts_with("three") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((3, 3)), |(a, _)| Some((a, 3))),
-            )
-        } else if line.starts_with('4') || line.starts_with("four") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((4, 4)), |(a, _)| Some((a, 4))),
-            )
-        } else if line.starts_with('5') || line.starts_with("five") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((5, 5)), |(a, _)| Some((a, 5))),
-            )
-        } else if line.starts_with('6') || line.starts_with("six") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((6, 6)), |(a, _)| Some((a, 6))),
-            )
-        } else if line.starts_with('7') || line.starts_with("seven") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((7, 7)), |(a, _)| Some((a, 7))),
-            )
-        } else if line.starts_with('8') || line.starts_with("eight") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((8, 8)), |(a, _)| Some((a, 8))),
-            )
-        } else if line.starts_with('9') || line.starts_with("nine") {
-            parse_line(
-                next,
-                digits.map_or_else(|| Some((9, 9)), |(a, _)| Some((a, 9))),
-            )
-        } else {
-            parse_line(next, digits)
+const WORDS: [&str; 10] = ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+
+/// The first and last digit in `line`, spelled out or not. Words can overlap, like `eightwo`.
+fn parse_line(line: &str) -> (i32, i32) {
+    let mut digits = None;
+    for (i, c) in line.char_indices() {
+        let rest = &line[i..];
+        let digit = c
+            .to_digit(10)
+            .map(|digit| digit as i32)
+            .or_else(|| WORDS.iter().position(|word| rest.starts_with(word)).map(|digit| digit as i32));
+        if let Some(digit) = digit {
+            digits = Some(digits.map_or((digit, digit), |(first, _)| (first, digit)));
         }
     }
+    digits.unwrap_or((0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("xtwone3four"), (2, 4));
+        assert_eq!(parse_line("é1abceightè"), (1, 8));
+        assert_eq!(parse_line(&"a".repeat(500_000)), (0, 0));
+    }
 }
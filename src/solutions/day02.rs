@@ -1,23 +1,38 @@
 use std::str::FromStr;
 
-use crate::{lines, Day};
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day02;
 
 impl Day for Day02 {
-    async fn part1(input: String) -> String {
-        let lines = lines(&input);
-        part1(&lines).to_string()
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+",
+        part1: Some("8"),
+        part2: Some("2286"),
+    }];
+
+    type Parsed = Vec<Game>;
+
+    fn parse(input: &Input) -> Result<Vec<Game>, SolveError> {
+        input.parse_lines()
+    }
+
+    fn part1(games: &Vec<Game>, _cx: &Context) -> Result<Answer, SolveError> {
+        part1(games).map(Into::into).ok_or(SolveError::Overflow)
     }
 
-    async fn part2(input: String) -> String {
-        let lines = lines(&input);
-        part2(&lines).to_string()
+    fn part2(games: &Vec<Game>, _cx: &Context) -> Result<Answer, SolveError> {
+        part2(games).map(Into::into).ok_or(SolveError::Overflow)
     }
 }
 
 
-struct Game {
+pub struct Game {
     number: i32,
     sets: Vec<Subset>,
 }
@@ -30,71 +45,72 @@ struct Subset {
 }
 
 impl FromStr for Game {
-    type Err = ();
+    type Err = SolveError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (game, sets) = s.split_once(": ").unwrap();
-        let number: i32 = game.split_at(5).1.parse().unwrap();
-        let sets = sets.split("; ").map(|set| set.parse().unwrap()).collect();
+        let (game, sets) = s
+            .split_once(": ")
+            .ok_or_else(|| SolveError::at_end(s, "`: ` after the game number"))?;
+        let number = game
+            .strip_prefix("Game ")
+            .ok_or_else(|| SolveError::at(s, game, "`Game ` followed by a number"))?;
+        let number = parse_at(s, number, "a game number")?;
+        let sets = sets
+            .split("; ")
+            .map(|set| set.parse().map_err(|err: SolveError| err.within(s, set)))
+            .collect::<Result<_, _>>()?;
         Ok(Self { number, sets })
     }
 }
 
 impl FromStr for Subset {
-    type Err = ();
+    type Err = SolveError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut slf = Self::default();
-        s.split(", ")
-            .for_each(|color| match color.split_once(' ').unwrap() {
-                (num, "green") => {
-                    slf.green = num.parse().unwrap();
-                }
-                (num, "blue") => {
-                    slf.blue = num.parse().unwrap();
-                }
-                (num, "red") => {
-                    slf.red = num.parse().unwrap();
-                }
-                _ => unreachable!(),
-            });
+        for color in s.split(", ") {
+            let (num, name) = color
+                .split_once(' ')
+                .ok_or_else(|| SolveError::at(s, color, "a count followed by a color"))?;
+            let count = match name {
+                "green" => &mut slf.green,
+                "blue" => &mut slf.blue,
+                "red" => &mut slf.red,
+                _ => { return Err(SolveError::at(s, name, "`red`, `green` or `blue`")); }
+            };
+            *count = parse_at(s, num, "a number of cubes")?;
+        }
         Ok(slf)
     }
 }
 
-fn part1(input: &[&str]) -> i32 {
-    input
+fn max_subset(sets: &[Subset]) -> Subset {
+    sets.iter()
+        .fold(Subset::default(), |set1, set2| Subset {
+            blue: std::cmp::max(set1.blue, set2.blue),
+            green: std::cmp::max(set1.green, set2.green),
+            red: std::cmp::max(set1.red, set2.red),
+        })
+}
+
+/// `None` if the sum doesn't fit in an `i32`
+fn part1(games: &[Game]) -> Option<i32> {
+    games
         .iter()
-        .map(|line| line.parse().unwrap())
         .filter_map(|Game { number, sets }| {
-            let max = sets
-                .into_iter()
-                .reduce(|set1, set2| Subset {
-                    blue: std::cmp::max(set1.blue, set2.blue),
-                    green: std::cmp::max(set1.green, set2.green),
-                    red: std::cmp::max(set1.red, set2.red),
-                })
-                .unwrap();
-            (max.red <= 12 && max.green <= 13 && max.blue <= 14).then_some(number)
+            let max = max_subset(sets);
+            (max.red <= 12 && max.green <= 13 && max.blue <= 14).then_some(*number)
         })
-        .sum()
+        .try_fold(0i32, i32::checked_add)
 }
 
-fn part2(input: &[&str]) -> i32 {
-    input
+/// `None` if a power or their sum doesn't fit in an `i32`
+fn part2(games: &[Game]) -> Option<i32> {
+    games
         .iter()
-        .map(|line| line.parse().unwrap())
-        .map(|Game { number: _, sets }| {
-            let min = sets
-                .into_iter()
-                .reduce(|set1, set2| Subset {
-                    blue: std::cmp::max(set1.blue, set2.blue),
-                    green: std::cmp::max(set1.green, set2.green),
-                    red: std::cmp::max(set1.red, set2.red),
-                })
-                .unwrap();
-            min.red * min.green * min.blue
+        .try_fold(0i32, |sum, Game { number: _, sets }| {
+            let min = max_subset(sets);
+            sum.checked_add(min.red.checked_mul(min.green)?.checked_mul(min.blue)?)
         })
-        .sum()
 }
 
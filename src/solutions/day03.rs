@@ -1,130 +1,120 @@
-use crate::{lines, Day};
+use crate::{
+    grid::{Grid, Pos},
+    Answer, Context, Day, Example, Input, SolveError,
+};
 
 pub struct Day03;
 
 impl Day for Day03 {
-    async fn part1(input: String) -> String {
-        part1(&to_schematic(&input)).to_string()
-    }
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..
+",
+        part1: Some("4361"),
+        part2: Some("467835"),
+    }];
+
+    type Parsed = Schematic;
 
-    async fn part2(input: String) -> String {
-        part2(&to_schematic(&input)).to_string()
+    fn parse(input: &Input) -> Result<Schematic, SolveError> {
+        Schematic::new(input)
     }
-}
 
+    fn part1(schematic: &Schematic, _cx: &Context) -> Result<Answer, SolveError> {
+        part1(schematic).map(Into::into).ok_or(SolveError::Overflow)
+    }
 
-#[derive(Debug)]
-struct Schematic {
-    numbers: Vec<Number>,
-    symbols: Vec<Symbol>,
+    fn part2(schematic: &Schematic, _cx: &Context) -> Result<Answer, SolveError> {
+        part2(schematic).map(Into::into).ok_or(SolveError::Overflow)
+    }
 }
 
-#[derive(Debug)]
-struct Number {
-    row: i32,
-    pos: i32,
-    value: i32,
-}
 
-#[derive(Debug)]
-struct Symbol {
-    row: i32,
-    pos: i32,
-    symbol: u8,
+pub struct Schematic {
+    grid: Grid<u8>,
+    numbers: Vec<i32>,
+    /// For every digit, which of `numbers` it's part of
+    number_at: Grid<Option<usize>>,
 }
 
-impl From<&[&str]> for Schematic {
-    fn from(lines: &[&str]) -> Self {
-        let mut numbers = Vec::new();
-        let mut symbols = Vec::new();
-        lines.iter().enumerate().for_each(|(row, line)| {
-            let mut iter = line.as_bytes().iter().enumerate().peekable();
-            while let Some((pos, b)) = iter.peek().copied() {
-                match *b {
-                    mut b @ b'0'..=b'9' => {
-                        let mut value = 0;
-                        // It pains me that `while let` and `if let` cannot have guards
-                        while b.is_ascii_digit() {
-                            value = value * 10 + (b - b'0') as i32;
-                            iter.next();
-                            if let Some((_, next)) = iter.peek() {
-                                b = **next;
-                            } else {
-                                break;
-                            }
-                        }
-                        numbers.push(Number {
-                            row: row as i32,
-                            pos: pos as i32,
-                            value,
-                        })
-                    }
-                    b'.' => {
-                        iter.next();
-                    }
-                    symbol => {
-                        symbols.push(Symbol {
-                            row: row as i32,
-                            pos: pos as i32,
-                            symbol,
-                        });
-                        iter.next();
-                    }
+impl Schematic {
+    fn new(input: &Input) -> Result<Self, SolveError> {
+        let grid = input.grid()?;
+        let mut numbers: Vec<i32> = Vec::new();
+        let mut number_at = Grid::new(grid.rows(), grid.cols(), None);
+        for row in 0..grid.rows() {
+            // Which of `numbers` the digits so far are, and the column it starts at
+            let mut current = None;
+            for (col, &b) in grid.row(row).iter().enumerate() {
+                if b.is_ascii_digit() {
+                    let (ind, start) = *current.get_or_insert_with(|| {
+                        numbers.push(0);
+                        (numbers.len() - 1, col)
+                    });
+                    numbers[ind] = numbers[ind]
+                        .checked_mul(10)
+                        .and_then(|number| number.checked_add((b - b'0') as i32))
+                        .ok_or_else(|| SolveError::at(input, &input.lines()[row][start..], "a number below 2^31"))?;
+                    number_at[Pos::new(row, col)] = Some(ind);
+                } else {
+                    current = None;
                 }
             }
-        });
+        }
+        Ok(Self { grid, numbers, number_at })
+    }
 
-        Self { numbers, symbols }
+    /// The numbers touching `pos`, diagonally included, each once
+    fn numbers_around(&self, pos: Pos) -> Vec<usize> {
+        let mut found: Vec<_> = self.grid
+            .neighbours8(pos)
+            .filter_map(|pos| self.number_at[pos])
+            .collect();
+        found.sort();
+        found.dedup();
+        found
     }
 }
 
-fn part1(schematic: &Schematic) -> i32 {
-    schematic
-        .numbers
-        .iter()
-        .filter_map(|number| {
-            schematic
-                .symbols
-                .iter()
-                .any(|symbol| symbol.next_to(number))
-                .then_some(number.value)
-        })
-        .sum()
+fn is_symbol(b: u8) -> bool {
+    b != b'.' && !b.is_ascii_digit()
 }
 
-impl Symbol {
-    fn next_to(&self, number: &Number) -> bool {
-        let length = number.value.checked_ilog10().unwrap_or(0) as i32;
-        let pos_min = number.pos - 1;
-        let pos_max = number.pos + 1 + length;
-        let row_min = number.row - 1;
-        let row_max = number.row + 1;
-        self.pos >= pos_min && self.pos <= pos_max && self.row >= row_min && self.row <= row_max
+/// `None` if the sum doesn't fit in an `i32`
+fn part1(schematic: &Schematic) -> Option<i32> {
+    let mut is_part = vec![false; schematic.numbers.len()];
+    for (pos, &b) in schematic.grid.iter() {
+        if is_symbol(b) {
+            for ind in schematic.numbers_around(pos) {
+                is_part[ind] = true;
+            }
+        }
     }
+    schematic.numbers
+        .iter()
+        .zip(is_part)
+        .filter_map(|(number, is_part)| is_part.then_some(number))
+        .try_fold(0i32, |sum, number| sum.checked_add(*number))
 }
 
-fn part2(schematic: &Schematic) -> i32 {
-    schematic
-        .symbols
+/// `None` if a gear ratio or their sum doesn't fit in an `i32`
+fn part2(schematic: &Schematic) -> Option<i32> {
+    schematic.grid
         .iter()
-        .filter_map(|symbol| {
-            let gears: Option<[i32; 2]> = schematic
-                .numbers
-                .iter()
-                .filter_map(|number| {
-                    (symbol.symbol == b'*' && symbol.next_to(number)).then_some(number.value)
-                })
-                .collect::<Vec<i32>>()
-                .try_into()
-                .ok();
-            gears
+        .filter(|(_, &b)| b == b'*')
+        .filter_map(|(pos, _)| match schematic.numbers_around(pos)[..] {
+            [a, b] => Some(schematic.numbers[a].checked_mul(schematic.numbers[b])),
+            _ => None,
         })
-        .map(|[a, b]| a * b)
-        .sum()
-}
-
-fn to_schematic(input: &str) -> Schematic {
-    Into::into(lines(input).as_slice())
+        .try_fold(0i32, |sum, ratio| sum.checked_add(ratio?))
 }
 
 #[cfg(test)]
@@ -133,18 +123,16 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input = "
-467..114..
-...*......
-..35..633.
-......#...
-617*......
-.....+.58.
-..592.....
-......755.
-...$.*....
-.664.598..";
-        assert_eq!(part1(&lines(input).as_slice().into()), 4361);
+        let input = Day03::EXAMPLES[0].input;
+        assert_eq!(part1(&Schematic::new(&input.into()).unwrap()), Some(4361));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let error = Day03::parse(&".............\n.99999999999.".into()).err();
+        assert!(matches!(error, Some(SolveError::Parse { line: 2, column: 2, .. })), "{error:?}");
+        let schematic = Day03::parse(&"99999*99999".into()).unwrap();
+        assert_eq!(Day03::part2(&schematic, &Context::new()), Err(SolveError::Overflow));
     }
 
     #[test]
@@ -1,90 +1,99 @@
 use std::str::FromStr;
 
-use crate::{lines, Day};
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day04;
 
 impl Day for Day04 {
-    async fn part1(input: String) -> String {
-        let cards: Vec<_> = lines(&input).iter().map(|line| line.parse().unwrap()).collect();
-        part1(&cards).to_string()
-    }
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+",
+        part1: Some("13"),
+        part2: Some("30"),
+    }];
 
-    async fn part2(input: String) -> String {
-        let cards: Vec<_> = lines(&input).iter().map(|line| line.parse().unwrap()).collect();
-        part2(cards).to_string()
+    type Parsed = Vec<Card>;
+
+    fn parse(input: &Input) -> Result<Vec<Card>, SolveError> {
+        input.parse_lines()
     }
-}
 
+    fn part1(cards: &Vec<Card>, _cx: &Context) -> Result<Answer, SolveError> {
+        part1(cards).map(Into::into).ok_or(SolveError::Overflow)
+    }
 
-type Winning = Vec<[u8; 3]>;
-type Given = Vec<[u8; 3]>;
+    fn part2(cards: &Vec<Card>, _cx: &Context) -> Result<Answer, SolveError> {
+        part2(cards).map(Into::into).ok_or(SolveError::Overflow)
+    }
+}
 
 #[derive(Debug)]
-struct Card {
-    winning: Winning,
-    given: Given,
+pub struct Card {
+    winning: Vec<u32>,
+    given: Vec<u32>,
 }
 
 impl FromStr for Card {
-    type Err = ();
+    type Err = SolveError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (winning, given) = s.split_once(':').unwrap().1.split_once(" |").unwrap();
-
-        let winning = winning
-            .as_bytes()
-            .chunks_exact(3)
-            .map(|chunk| chunk.try_into().unwrap())
-            .collect::<Vec<[u8; 3]>>();
-        let given = given
-            .as_bytes()
-            .chunks_exact(3)
-            .map(|chunk| chunk.try_into().unwrap())
-            .collect::<Vec<[u8; 3]>>();
+        let (_, numbers) = s
+            .split_once(':')
+            .ok_or_else(|| SolveError::at_end(s, "`:` after the card number"))?;
+        let (winning, given) = numbers
+            .split_once(" |")
+            .ok_or_else(|| SolveError::at_end(s, "` |` between the winning and the given numbers"))?;
+
+        let numbers = |numbers: &str| {
+            numbers
+                .split_whitespace()
+                .map(|num| parse_at(s, num, "a number"))
+                .collect::<Result<Vec<u32>, _>>()
+        };
+        let (winning, given) = (numbers(winning)?, numbers(given)?);
 
         Ok(Card { winning, given })
     }
 }
 
 impl Card {
-    fn match_count(&self) -> i64 {
+    fn match_count(&self) -> usize {
         self.given
             .iter()
             .filter(|num| self.winning.contains(num))
-            .count() as i64
+            .count()
     }
 
-    fn score(&self) -> i64 {
-        let num = self.match_count();
-        if num == 0 {
-            0
-        } else {
-            1 << (num - 1)
+    /// `None` when it doesn't fit in a `u64`, from 65 matches on
+    fn score(&self) -> Option<u64> {
+        match self.match_count() {
+            0 => Some(0),
+            num => 1u64.checked_shl(u32::try_from(num - 1).ok()?),
         }
     }
 }
 
-fn part1(cards: &[Card]) -> i64 {
-    cards.iter().map(|card| card.score()).sum()
+fn part1(cards: &[Card]) -> Option<u64> {
+    cards.iter().try_fold(0u64, |sum, card| sum.checked_add(card.score()?))
 }
 
-fn part2(cards: Vec<Card>) -> i64 {
-    let mut cards = cards.into_iter().map(|card| (card, 1)).collect::<Vec<_>>();
+fn part2(cards: &[Card]) -> Option<u64> {
+    let mut copies = vec![1u64; cards.len()];
 
-    for i in 0..cards.len() {
-        let (card, copies) = &cards[i];
+    for (i, card) in cards.iter().enumerate() {
         let num = card.match_count();
-        let copies = *copies;
-
-        for j in 0..num {
-            if let Some((_, n)) = cards.get_mut(i + j as usize + 1) {
-                *n += copies;
-            }
+        let (won_by, later) = copies.split_at_mut(i + 1);
+        for n in later.iter_mut().take(num) {
+            *n = n.checked_add(won_by[i])?;
         }
     }
 
-    cards.into_iter().map(|(_, num)| num).sum()
+    copies.into_iter().try_fold(0u64, u64::checked_add)
 }
 
 #[cfg(test)]
@@ -93,19 +102,18 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        let input = "
-Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
-Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
-Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
-Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
-Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
-Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
-";
-        let cards = input
-            .trim()
-            .split('\n')
-            .map(|line| line.parse().unwrap())
-            .collect();
-        assert_eq!(30, part2(cards));
+        let input = Day04::EXAMPLES[0].input;
+        let cards = Day04::parse(&input.into()).unwrap();
+        assert_eq!(Some(30), part2(&cards));
+    }
+
+    #[test]
+    fn test_bad_cards() {
+        let error = Day04::parse(&"Card 1: 41 4x | 83\n".into()).unwrap_err();
+        assert!(matches!(error, SolveError::Parse { line: 1, column: 12, .. }), "{error:?}");
+
+        let numbers = (1..=65).map(|n| n.to_string()).collect::<Vec<_>>().join(" ");
+        let cards = Day04::parse(&format!("Card 1: {numbers} | {numbers}").into()).unwrap();
+        assert_eq!(Day04::part1(&cards, &Context::new()), Err(SolveError::Overflow));
     }
 }
@@ -1,64 +1,99 @@
 use std::str::FromStr;
 
-use crate::Day;
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day05;
 
 impl Day for Day05 {
-    async fn part1(input: String) -> String {
-        let mut chunks = input.trim().split("\n\n");
-        let seeds: Vec<_> = chunks
-            .next()
-            .unwrap()
-            .split(' ')
-            .skip(1)
-            .map(|x| x.parse().unwrap())
-            .collect();
-        let maps: Vec<Vec<Range>> = chunks
-            .map(|chunk| {
-                chunk
-                    .split('\n')
-                    .skip(1)
-                    .map(|line| line.parse().unwrap())
-                    .collect()
-            })
-            .collect();
-        let maps_refs = maps.iter().map(|vec| vec.as_slice()).collect::<Vec<_>>();
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "seeds: 79 14 55 13
 
-        part1(seeds, &maps_refs).to_string()
-    }
+seed-to-soil map:
+50 98 2
+52 50 48
 
-    async fn part2(input: String) -> String {
-        let mut chunks = input.trim().split("\n\n");
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+",
+        part1: Some("35"),
+        part2: Some("46"),
+    }];
+
+    type Parsed = Almanac;
+
+    fn parse(input: &Input) -> Result<Almanac, SolveError> {
+        let mut chunks = input.sections().into_iter();
         let seeds: Vec<i64> = chunks
             .next()
-            .unwrap()
+            .unwrap_or_default()
             .split(' ')
             .skip(1)
-            .map(|x| x.parse().unwrap())
-            .collect();
-        let seeds = seeds.chunks_exact(2).map(|arr| (arr[0], arr[0] + arr[1])).collect();
+            .map(|x| parse_at(input, x, "a seed number"))
+            .collect::<Result<_, _>>()?;
+        if seeds.is_empty() {
+            return Err(SolveError::at(input, input, "`seeds:` followed by at least one seed number"));
+        }
+        let seed_ranges = if seeds.len().is_multiple_of(2) {
+            seeds
+                .chunks_exact(2)
+                .map(|arr| Some((arr[0], arr[0].checked_add(arr[1])?)))
+                .collect::<Option<_>>()
+                .ok_or(SolveError::Overflow)
+        } else {
+            Err(SolveError::at(input, input.lines()[0], "seed ranges given as pairs of start and length"))
+        };
         let maps: Vec<Vec<Range>> = chunks
             .map(|chunk| {
                 chunk
                     .split('\n')
                     .skip(1)
-                    .map(|line| line.parse().unwrap())
+                    .map(|line| line.parse().map_err(|err: SolveError| err.within(input, line)))
                     .collect()
             })
-            .collect();
-        let maps_refs = maps.iter().map(|vec| vec.as_slice()).collect::<Vec<_>>();
+            .collect::<Result<_, _>>()?;
+        Ok(Almanac { seeds, seed_ranges, maps })
+    }
 
-        part2(seeds, &maps_refs).to_string()
+    fn part1(almanac: &Almanac, _cx: &Context) -> Result<Answer, SolveError> {
+        part1(&almanac.seeds, &almanac.maps).map(Into::into).ok_or(SolveError::Overflow)
     }
-}
 
+    fn part2(almanac: &Almanac, _cx: &Context) -> Result<Answer, SolveError> {
+        let seeds = almanac.seed_ranges.as_ref().map_err(Clone::clone)?;
+        part2(seeds, &almanac.maps).map(Into::into).ok_or(SolveError::Overflow)
+    }
+}
 
-pub fn read_input() -> String {
-    std::fs::read_to_string("input/day05.txt")
-        .unwrap()
-        .trim()
-        .replace('\r', "")
+pub struct Almanac {
+    seeds: Vec<i64>,
+    /// The seeds read as pairs of start and length, the way part 2 has them, or why they can't be
+    seed_ranges: Result<Vec<(i64, i64)>, SolveError>,
+    maps: Vec<Vec<Range>>,
 }
 
 #[derive(Debug)]
@@ -69,26 +104,27 @@ struct Range {
 }
 
 impl FromStr for Range {
-    type Err = ();
+    type Err = SolveError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let [dest, src, length] = s
+        let [dest, src, length]: [&str; 3] = s
             .split(' ')
-            .map(|x| x.parse().unwrap())
-            .collect::<Vec<i64>>()
+            .collect::<Vec<_>>()
             .try_into()
-            .unwrap();
-        Ok(Self {
-            dest,
-            src,
-            src_end: src + length,
-        })
+            .map_err(|_| SolveError::at(s, s, "a destination start, a source start and a length"))?;
+        let dest = parse_at(s, dest, "a number")?;
+        let src: i64 = parse_at(s, src, "a number")?;
+        let src_end = src
+            .checked_add(parse_at(s, length, "a number")?)
+            .ok_or_else(|| SolveError::at(s, length, "a length that keeps the range's end below 2^63"))?;
+        Ok(Self { dest, src, src_end })
     }
 }
 
 impl Range {
-    const fn translate(&self, num: i64) -> i64 {
-        num - self.src + self.dest
+    /// `None` when the number it maps to doesn't fit
+    fn translate(&self, num: i64) -> Option<i64> {
+        num.checked_sub(self.src)?.checked_add(self.dest)
     }
 
     const fn contains(&self, num: i64) -> bool {
@@ -96,51 +132,44 @@ impl Range {
     }
 }
 
-fn part1(mut seeds: Vec<i64>, maps: &[&[Range]]) -> i64 {
+/// The lowest location, or `None` if a number stops fitting on the way there
+fn part1(seeds: &[i64], maps: &[Vec<Range>]) -> Option<i64> {
+    let mut seeds = seeds.to_vec();
     for map in maps.iter() {
         seeds = seeds
             .into_iter()
-            .map(|x| {
-                map.iter()
-                    .find_map(|range| {
-                        (range.src <= x && x <= range.src_end).then_some(range.translate(x))
-                    })
-                    .unwrap_or(x)
+            .map(|x| match map.iter().find(|range| range.contains(x)) {
+                Some(range) => range.translate(x),
+                None => Some(x),
             })
-            .collect();
+            .collect::<Option<_>>()?;
     }
-    seeds.into_iter().min().unwrap()
+    seeds.into_iter().min()
 }
 
-fn part2(mut seeds: Vec<(i64, i64)>, maps: &[&[Range]]) -> i64 {
+/// The lowest location, or `None` if a number stops fitting on the way there
+fn part2(seeds: &[(i64, i64)], maps: &[Vec<Range>]) -> Option<i64> {
+    let mut seeds = seeds.to_vec();
     for map in maps {
         let cap = seeds.len() << 1;
         let mut old_seeds = std::mem::replace(&mut seeds, Vec::with_capacity(cap));
         while let Some((min, max)) = old_seeds.pop() {
-            let seed = map
-                .iter()
-                .find_map(|range| {
-                    if range.contains(min) && range.contains(max) {
-                        Some((range.translate(min), range.translate(max)))
-                    } else if range.contains(min) {
-                        old_seeds.push((range.src_end + 1, max));
-                        Some((range.translate(min), range.translate(range.src_end)))
-                    } else if range.contains(max) {
-                        old_seeds.push((min, range.src - 1));
-                        Some((range.translate(range.src), range.translate(max)))
-                    } else if min < range.src && range.src_end < max {
-                        old_seeds.push((min, range.src - 1));
-                        old_seeds.push((range.src_end + 1, max));
-                        Some((range.translate(range.src), range.translate(range.src_end)))
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or((min, max));
-            seeds.push(seed);
+            let Some(range) = map.iter().find(|range| min <= range.src_end && range.src <= max) else {
+                seeds.push((min, max));
+                continue;
+            };
+            // The parts sticking out either side get mapped on their own
+            let (start, end) = (min.max(range.src), max.min(range.src_end));
+            if min < start {
+                old_seeds.push((min, start - 1));
+            }
+            if end < max {
+                old_seeds.push((end + 1, max));
+            }
+            seeds.push((range.translate(start)?, range.translate(end)?));
         }
     }
-    seeds.into_iter().map(|(min, _)| min).min().unwrap()
+    seeds.into_iter().map(|(min, _)| min).min()
 }
 
 #[cfg(test)]
@@ -149,67 +178,26 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        let input = "seeds: 79 14 55 13
-
-seed-to-soil map:
-50 98 2
-52 50 48
-
-soil-to-fertilizer map:
-0 15 37
-37 52 2
-39 0 15
-
-fertilizer-to-water map:
-49 53 8
-0 11 42
-42 0 7
-57 7 4
-
-water-to-light map:
-88 18 7
-18 25 70
-
-light-to-temperature map:
-45 77 23
-81 45 19
-68 64 13
+        let almanac = Day05::parse(&Day05::EXAMPLES[0].input.into()).unwrap();
+        assert_eq!(part2(almanac.seed_ranges.as_ref().unwrap(), &almanac.maps), Some(46));
+    }
 
-temperature-to-humidity map:
-0 69 1
-1 0 69
+    #[test]
+    fn test_odd_seeds() {
+        let input = Input::from("seeds: 1 2 3\n\nseed-to-soil map:\n50 98 2");
+        let almanac = Day05::parse(&input).unwrap();
+        assert_eq!(Day05::part1(&almanac, &Context::new()), Ok(Answer::Signed(1)));
+        assert!(matches!(Day05::part2(&almanac, &Context::new()), Err(SolveError::Parse { line: 1, .. })));
+    }
 
-humidity-to-location map:
-60 56 37
-56 93 4";
-        let mut chunks = input.split("\n\n");
-        let seeds: Vec<_> = chunks
-            .next()
-            .and_then(|x| x.strip_prefix("seeds: "))
-            .unwrap()
-            .split(' ')
-            .map(|x| x.parse().unwrap())
-            .collect();
-        let maps: Vec<Vec<Range>> = chunks
-            .map(|chunk| {
-                chunk
-                    .split('\n')
-                    .skip(1)
-                    .map(|line| line.parse().unwrap())
-                    .collect()
-            })
-            .collect();
-        let maps_refs = maps.iter().map(|vec| vec.as_slice()).collect::<Vec<_>>();
-
-        assert_eq!(
-            part2(
-                seeds
-                    .chunks_exact(2)
-                    .map(|slice| (slice[0], slice[0] + slice[1]))
-                    .collect(),
-                maps_refs.as_slice()
-            ),
-            46
-        );
+    #[test]
+    fn test_overflow() {
+        let input = Input::from("seeds: 1 2\n\nseed-to-soil map:\n50 98 9223372036854775807");
+        assert!(matches!(Day05::parse(&input), Err(SolveError::Parse { line: 4, column: 7, .. })));
+
+        let input = Input::from("seeds: 9223372036854775807 1\n\nseed-to-soil map:\n9223372036854775807 0 9000000000000000000");
+        let almanac = Day05::parse(&input).unwrap();
+        assert_eq!(Day05::part1(&almanac, &Context::new()), Err(SolveError::Overflow));
+        assert_eq!(Day05::part2(&almanac, &Context::new()), Err(SolveError::Overflow));
     }
 }
@@ -1,56 +1,94 @@
-use crate::{lines, Day};
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day06;
 
 impl Day for Day06 {
-    async fn part1(input: String) -> String {
-        part1(&lines(&input)).to_string()
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "Time:      7  15   30
+Distance:  9  40  200
+",
+        part1: Some("288"),
+        part2: Some("71503"),
+    }];
+
+    type Parsed = Races;
+
+    fn parse(input: &Input) -> Result<Races, SolveError> {
+        let [times, distances]: [&str; 2] = input
+            .lines()
+            .try_into()
+            .map_err(|_| SolveError::at(input, input, "a `Time:` line and a `Distance:` line"))?;
+        let kerned = kerned_number(input, times)
+            .and_then(|time| Ok((time, kerned_number(input, distances)?)));
+        let (times, distances) = (numbers(input, times)?, numbers(input, distances)?);
+        if times.len() != distances.len() {
+            return Err(SolveError::at(input, input.lines()[1], "as many distances as times"));
+        }
+        Ok(Races { races: times.into_iter().zip(distances).collect(), kerned })
+    }
+
+    fn part1(races: &Races, _cx: &Context) -> Result<Answer, SolveError> {
+        part1(&races.races).map(Into::into).ok_or(SolveError::Overflow)
     }
 
-    async fn part2(input: String) -> String {
-        part2(&lines(&input)).to_string()
+    fn part2(races: &Races, _cx: &Context) -> Result<Answer, SolveError> {
+        Ok(ways_to_win(races.kerned.clone()?).into())
     }
 }
 
+pub struct Races {
+    /// Each race's time and record distance
+    races: Vec<(f64, f64)>,
+    /// The one long race part 2 reads once the spaces are taken out, or why it couldn't be read
+    kerned: Result<(f64, f64), SolveError>,
+}
 
-fn part1(input: &[&str]) -> i64 {
-    let times: Vec<_> = input[0]
-        .split_whitespace()
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    let distances: Vec<_> = input[1]
+/// The numbers after the label. They're whole and not negative, but `ways_to_win` wants them as floats.
+fn numbers(input: &str, line: &str) -> Result<Vec<f64>, SolveError> {
+    let (_, numbers) = line
+        .split_once(':')
+        .ok_or_else(|| SolveError::at_end(line, "`:` after the label").within(input, line))?;
+    numbers
         .split_whitespace()
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    times.into_iter().zip(distances).map(ways_to_win).product()
+        .map(|number| parse_at(input, number, "a number").map(|number: u64| number as f64))
+        .collect()
+}
+
+/// `None` if the product doesn't fit in an `i64`
+fn part1(races: &[(f64, f64)]) -> Option<i64> {
+    races.iter().copied().map(ways_to_win).try_fold(1i64, i64::checked_mul)
 }
 
 fn ways_to_win((time, dist): (f64, f64)) -> i64 {
-    let odd = time % 2.;
     // calculate discriminant (the square root in the quadratic formula)
     let discriminant = (time * time - 4. * dist).sqrt();
-    let rem = discriminant % 2.;
-    if (odd == 1. && rem > 1.) || (odd == 0. && rem < 1.) {
-        discriminant.ceil() as i64
-    } else {
-        discriminant.floor() as i64
-    }
+    // only the whole hold times strictly between the roots beat the record
+    let shortest = ((time - discriminant) / 2.).floor() as i64 + 1;
+    let longest = ((time + discriminant) / 2.).ceil() as i64 - 1;
+    (longest - shortest + 1).max(0)
 }
 
-fn part2(input: &[&str]) -> i64 {
-    let time = input[0]
-        .split_once(':')
-        .unwrap()
-        .1
-        .replace(' ', "")
-        .parse::<f64>()
-        .unwrap();
-    let dist = input[1]
+/// Reads the digits after the `:` as one number, ignoring the spaces between them
+fn kerned_number(input: &str, line: &str) -> Result<f64, SolveError> {
+    let (_, digits) = line
         .split_once(':')
-        .unwrap()
-        .1
+        .ok_or_else(|| SolveError::at_end(line, "`:` after the label").within(input, line))?;
+    digits
         .replace(' ', "")
-        .parse::<f64>()
-        .unwrap();
-    ways_to_win((time, dist))
+        .parse()
+        .map(|number: u64| number as f64)
+        .map_err(|_| SolveError::at(input, digits, "a number, possibly split up by spaces"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bad_numbers() {
+        let error = Day06::parse(&"Time: 7 1x\nDistance: 9 40".into()).err();
+        assert!(matches!(error, Some(SolveError::Parse { line: 1, column: 9, .. })), "{error:?}");
+        let error = Day06::parse(&"Time: 7 15\nDistance: 9".into()).err();
+        assert!(matches!(error, Some(SolveError::Parse { line: 2, .. })), "{error:?}");
+    }
 }
@@ -1,41 +1,63 @@
 use std::cmp::Ordering;
 
-use crate::{Day, lines};
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day07;
 
 impl Day for Day07 {
-    async fn part1(input: String) -> String {
-        let lines = lines(&input);
-        let mut hands: Vec<_> = lines.iter()
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483
+",
+        part1: Some("6440"),
+        part2: None,
+    }];
+
+    type Parsed = Vec<Hand>;
+
+    fn parse(input: &Input) -> Result<Vec<Hand>, SolveError> {
+        input.lines().into_iter()
             .map(|line| {
-                let (cards, bid) = line.split_once(' ').unwrap();
-                Hand {
-                    r#type: hand_type(cards.as_bytes()),
-                    cards: cards.as_bytes().iter().map(|&b| b.into()).collect::<Vec<Card>>().try_into().unwrap(),
-                    bid: bid.parse().unwrap()
-                }
+                let (cards, bid) = line
+                    .split_once(' ')
+                    .ok_or_else(|| SolveError::at_end(line, "a space between the hand and the bid").within(input, line))?;
+                let cards = parse_cards(input, cards)?;
+                Ok(Hand { r#type: hand_type(&cards), cards, bid: parse_at(input, bid, "a bid")? })
             })
-            .collect();
-        hands.sort();
+            .collect()
+    }
 
-        let cards: Vec<_> = hands.iter().filter_map(|hand| (hand.r#type == Type::High).then_some(&hand.cards)).collect();
-        let mut sorted_cards = cards.clone();
-        sorted_cards.sort();
-        assert_eq!(cards, sorted_cards);
+    fn part1(hands: &Vec<Hand>, _cx: &Context) -> Result<Answer, SolveError> {
+        let mut hands: Vec<&Hand> = hands.iter().collect();
+        hands.sort();
 
-        let ans: usize = hands
+        let ans = hands
             .into_iter()
             .enumerate()
-            .map(|(ind, hand)| -> usize {
-                (ind + 1) * hand.bid
-            })
-            .sum();
+            .try_fold(0usize, |sum, (ind, hand)| sum.checked_add((ind + 1).checked_mul(hand.bid)?))
+            .ok_or(SolveError::Overflow)?;
 
-        ans.to_string()
+        Ok(ans.into())
     }
 }
 
+fn parse_cards(input: &str, cards: &str) -> Result<[Card; 5], SolveError> {
+    cards
+        .char_indices()
+        .map(|(ind, c)| {
+            u8::try_from(c)
+                .ok()
+                .and_then(|b| Card::try_from(b).ok())
+                .ok_or_else(|| SolveError::at(input, &cards[ind..ind + c.len_utf8()], "a card (`2`-`9`, `T`, `J`, `Q`, `K` or `A`)"))
+        })
+        .collect::<Result<Vec<Card>, _>>()?
+        .try_into()
+        .map_err(|_| SolveError::at(input, cards, "a hand of five cards"))
+}
+
 
 #[repr(u8)]
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -95,9 +117,11 @@ impl Ord for Card {
     }
 }
 
-impl From<u8> for Card {
-    fn from(value: u8) -> Self {
-        match value {
+impl TryFrom<u8> for Card {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
             b'2' => Card::II,
             b'3' => Card::III,
             b'4' => Card::IV,
@@ -111,13 +135,13 @@ impl From<u8> for Card {
             b'Q' => Card::Q,
             b'K' => Card::K,
             b'A' => Card::A,
-            _ => unreachable!("{}", value as char),
-        }
+            _ => { return Err(value); }
+        })
     }
 }
 
 #[derive(PartialEq, Eq, Debug)]
-struct Hand {
+pub struct Hand {
     r#type: Type,
     cards: [Card; 5],
     bid: usize,
@@ -139,22 +163,15 @@ impl Ord for Hand {
     }
 }
 
-fn hand_type(cards: &[u8]) -> Type {
-    let mut counts = [(0u8, 0u8); 4];
-    cards.iter().for_each(|b| {
-        counts.iter_mut().find_map(|(key, val)| {
-            if b == key || key == &0u8 {
-                *key = *b;
-                *val += 1;
-                Some(())
-            } else {
-                None
-            }
-        });
-    });
+fn hand_type(cards: &[Card; 5]) -> Type {
+    // How many of each card, indexed by its value
+    let mut counts = [0u8; Card::A as usize + 1];
+    for &card in cards {
+        counts[card as usize] += 1;
+    }
     counts
         .iter()
-        .fold(Type::High, |r#type, (_, val)| match (r#type, val) {
+        .fold(Type::High, |r#type, val| match (r#type, val) {
             (_, 5) => Type::Five,
             (_, 4) => Type::Four,
             (Type::Three, 2) | (Type::Pair, 3) => Type::Full,
@@ -176,12 +193,12 @@ mod tests {
             ("JJJAJ", Type::Four),
             ("2T2T2", Type::Full),
             ("99329", Type::Three),
-            ("19129", Type::TwoPair),
-            ("18129", Type::Pair),
-            ("38129", Type::High),
+            ("A9A29", Type::TwoPair),
+            ("A8A29", Type::Pair),
+            ("38K29", Type::High),
         ];
         for (hand, ans) in cases.into_iter() {
-            assert_eq!(hand_type(hand.as_bytes()), ans);
+            assert_eq!(hand_type(&parse_cards(hand, hand).unwrap()), ans);
         }
     }
 
@@ -221,4 +238,13 @@ mod tests {
 
         assert_eq!(actual, test_hands);
     }
+
+    #[test]
+    fn test_bad_hands() {
+        let input = Input::from(format!("{} 5", "J".repeat(300)));
+        assert!(matches!(Day07::parse(&input), Err(SolveError::Parse { line: 1, column: 1, .. })));
+
+        let hands = Day07::parse(&"AAAAA 18446744073709551615\n22222 2".into()).unwrap();
+        assert_eq!(Day07::part1(&hands, &Context::new()), Err(SolveError::Overflow));
+    }
 }
@@ -1,133 +1,178 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc, thread::{self, JoinHandle}};
+use std::collections::HashMap;
 
 use num::integer::lcm;
 
-use crate::{lines, Day};
+use crate::{Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day08;
 
 impl Day for Day08 {
-    async fn part1(input: String) -> String {
-        let lines = lines(&input);
+    const EXAMPLES: &'static [Example] = &[
+        Example {
+            input: "RL
+
+AAA = (BBB, CCC)
+BBB = (DDD, EEE)
+CCC = (ZZZ, GGG)
+DDD = (DDD, DDD)
+EEE = (EEE, EEE)
+GGG = (GGG, GGG)
+ZZZ = (ZZZ, ZZZ)
+",
+            part1: Some("2"),
+            part2: None,
+        },
+        Example {
+            input: "LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)
+",
+            part1: Some("6"),
+            part2: None,
+        },
+        Example {
+            input: "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+",
+            part1: None,
+            part2: Some("6"),
+        },
+    ];
+
+    type Parsed = Network;
+
+    fn parse(input: &Input) -> Result<Network, SolveError> {
+        let lines = input.lines();
         let mut lines_iter = lines.iter();
-        let instructions: Arc<[u8]> = Arc::from(lines_iter.next().unwrap().trim().as_bytes());
-        let map: HashMap<[u8; 3], Node> = lines_iter
+        let first = lines_iter.next().unwrap_or(&"");
+        if first.is_empty() {
+            return Err(SolveError::at(input, first, "a line of `L` and `R` instructions"));
+        }
+        if let Some(ind) = first.find(|c| c != 'L' && c != 'R') {
+            let end = first[ind..].chars().next().map_or(ind, |c| ind + c.len_utf8());
+            return Err(SolveError::at(input, &first[ind..end], "`L` or `R`"));
+        }
+        let instructions = first.as_bytes().to_vec();
+
+        let (map, targets): (Map, Vec<[&str; 2]>) = lines_iter
             .skip(1)
-            .map(|line| {
-                (
-                    line.as_bytes()[..3].try_into().unwrap(),
-                    line[6..].parse().unwrap(),
-                )
-            })
-            .collect();
-
-        part1(&instructions, &map).to_string()
+            .map(|line| parse_node(input, line).map(|(name, node, targets)| ((name, node), targets)))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+
+        // Check every node leads somewhere so walking the map can't get lost
+        if let Some(label) = targets.iter().flatten().find(|label| !map.contains_key(label.as_bytes())) {
+            return Err(SolveError::at(input, label, "the name of a node in the map"));
+        }
+
+        let start = if map.contains_key(b"AAA") {
+            Ok(*b"AAA")
+        } else {
+            Err(SolveError::at_end(input, "a node named `AAA`"))
+        };
+        let ghosts: Vec<[u8; 3]> = map.keys().filter(|key| key[2] == b'A').copied().collect();
+        let ghosts = if ghosts.is_empty() {
+            Err(SolveError::at_end(input, "a node whose name ends in `A`"))
+        } else {
+            Ok(ghosts)
+        };
+        Ok(Network { instructions, map, start, ghosts })
     }
 
-    async fn part2(input: String) -> String {
-        let lines = lines(&input);
-        let mut lines_iter = lines.iter();
-        let instructions: Arc<[u8]> = Arc::from(lines_iter.next().unwrap().trim().as_bytes());
-        let map: HashMap<[u8; 3], Node> = lines_iter
-            .skip(1)
-            .map(|line| {
-                (
-                    line.as_bytes()[..3].try_into().unwrap(),
-                    line[6..].parse().unwrap(),
-                )
-            })
-            .collect();
-
-        part2(instructions, Arc::new(map)).to_string()
+    fn part1(network: &Network, cx: &Context) -> Result<Answer, SolveError> {
+        Ok(part1(network, cx)?.into())
+    }
+
+    fn part2(network: &Network, cx: &Context) -> Result<Answer, SolveError> {
+        Ok(part2(network, cx)?.into())
     }
 }
 
+type Map = HashMap<[u8; 3], Node>;
+
+pub struct Network {
+    instructions: Vec<u8>,
+    map: Map,
+    /// Where part 1 starts, or why it can't
+    start: Result<[u8; 3], SolveError>,
+    /// Where part 2's ghosts start, or why there aren't any
+    ghosts: Result<Vec<[u8; 3]>, SolveError>,
+}
+
+/// Parses `AAA = (BBB, CCC)`, keeping the slices of the targets around for error reporting
+fn parse_node<'a>(input: &str, line: &'a str) -> Result<([u8; 3], Node, [&'a str; 2]), SolveError> {
+    let (name, targets) = line
+        .split_once(" = ")
+        .ok_or_else(|| SolveError::at_end(line, "` = ` after the node name").within(input, line))?;
+    let (l, r) = targets
+        .strip_prefix('(')
+        .and_then(|targets| targets.strip_suffix(')'))
+        .and_then(|targets| targets.split_once(", "))
+        .ok_or_else(|| SolveError::at(input, targets, "two node names like `(BBB, CCC)`"))?;
+    let node = Node {
+        l: label(input, l)?,
+        r: label(input, r)?,
+    };
+    Ok((label(input, name)?, node, [l, r]))
+}
+
+fn label(input: &str, s: &str) -> Result<[u8; 3], SolveError> {
+    s.as_bytes()
+        .try_into()
+        .map_err(|_| SolveError::at(input, s, "a three letter node name"))
+}
+
 #[derive(Debug)]
 struct Node {
     l: [u8; 3],
     r: [u8; 3],
 }
 
-impl FromStr for Node {
-    type Err = ();
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            l: s.as_bytes()[1..4].try_into().unwrap(),
-            r: s.as_bytes()[6..9].try_into().unwrap(),
-        })
-    }
+fn part1(network: &Network, cx: &Context) -> Result<usize, SolveError> {
+    steps(network.start.clone()?, |pos| pos == [b'Z'; 3], network, cx)
 }
 
-fn part1(instructions: &[u8], map: &HashMap<[u8; 3], Node>) -> usize {
-    let mut pos = [b'A'; 3];
-    instructions
-        .iter()
-        .cycle()
-        .enumerate()
-        .find_map(|(step, ins)| {
-            if pos == [b'Z'; 3] {
-                Some(step)
-            } else if ins == &b'L' {
-                pos = map.get(&pos).unwrap().l;
-                None
-            } else {
-                pos = map.get(&pos).unwrap().r;
-                None
-            }
-        })
-        .unwrap()
-}
+fn part2(network: &Network, cx: &Context) -> Result<usize, SolveError> {
+    let ghosts = network.ghosts.as_ref().map_err(Clone::clone)?;
 
-// I was going to get rid of this but it was a convenient way to capture the starting position
-#[derive(Debug)]
-struct Ghost {
-    pos: [u8; 3],
+    tracing::debug!("starting {} ghosts", ghosts.len());
+    let mut total = 1;
+    for (ind, &ghost) in ghosts.iter().enumerate() {
+        cx.progress(ind as u64, ghosts.len() as u64);
+        total = lcm(total, steps(ghost, |pos| pos[2] == b'Z', network, cx)?);
+    }
+    Ok(total)
 }
 
-fn part2(instructions: Arc<[u8]>, map: Arc<HashMap<[u8; 3], Node>>) -> usize {
-    let ghosts: Vec<Ghost> = map
-        .keys()
-        .filter_map(|key| {
-            if key[2] == b'A' {
-                Some(Ghost {
-                    pos: *key,
-                })
-            } else {
-                None
+/// Counts the steps from `start` to a node where `is_end` holds. Maps that never get there would
+/// walk forever, so it checks `cx` after every pass through the instructions.
+fn steps(
+    mut pos: [u8; 3],
+    is_end: impl Fn([u8; 3]) -> bool,
+    network: &Network,
+    cx: &Context,
+) -> Result<usize, SolveError> {
+    let mut step = 0;
+    loop {
+        for ins in &network.instructions {
+            if is_end(pos) {
+                return Ok(step);
             }
-        })
-        .collect();
-
-    println!("Starting {} ghosts", ghosts.len());
-    let handles: Vec<JoinHandle<usize>> = ghosts
-        .into_iter()
-        .map(|mut ghost| {
-            let cloned_instructions = instructions.clone();
-            let cloned_map = map.clone();
-            thread::spawn(move || {
-                cloned_instructions.iter()
-                    .cycle()
-                    .enumerate()
-                    .find_map(|(step, ins)| {
-                        if ghost.pos[2] == b'Z' {
-                            Some(step)
-                        } else {
-                            if ins == &b'L' {
-                                ghost.pos = cloned_map.get(&ghost.pos).unwrap().l;
-                            } else {
-                                ghost.pos = cloned_map.get(&ghost.pos).unwrap().r;
-                            }
-                            None
-                        }
-                    })
-                    .unwrap()
-            })
-        })
-        .collect();
-
-    handles
-        .into_iter()
-        .map(|handle| handle.join().unwrap())
-        .reduce(lcm)
-        .unwrap()
+            let node = &network.map[&pos];
+            pos = if *ins == b'L' { node.l } else { node.r };
+            step += 1;
+        }
+        cx.check()?;
+    }
 }
@@ -1,30 +1,46 @@
-use num::{Integer, BigInt};
-use crate::{lines, Day};
+use num::{BigInt, CheckedAdd, CheckedSub, Integer};
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day09;
 
 impl Day for Day09 {
-    async fn part1(input: String) -> String {
-        let sequences: Vec<Vec<i64>> = lines(&input).iter().map(|line| line.split(' ').map(|x| x.parse().unwrap()).collect()).collect();
-
-        part1(&sequences).to_string()
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "0 3 6 9 12 15
+1 3 6 10 15 21
+10 13 16 21 30 45
+",
+        part1: Some("114"),
+        part2: Some("2"),
+    }];
+
+    type Parsed = Vec<Vec<i64>>;
+
+    fn parse(input: &Input) -> Result<Vec<Vec<i64>>, SolveError> {
+        input
+            .lines()
+            .iter()
+            .map(|line| line.split(' ').map(|x| parse_at(input, x, "a number")).collect())
+            .collect()
     }
 
-    async fn part2(input: String) -> String {
-        let sequences: Vec<Vec<i64>> = lines(&input).iter().map(|line| line.split(' ').map(|x| x.parse().unwrap()).collect()).collect();
+    fn part1(sequences: &Vec<Vec<i64>>, _cx: &Context) -> Result<Answer, SolveError> {
+        part1(sequences).map(Into::into).ok_or(SolveError::Overflow)
+    }
 
-        part2(&sequences).to_string()
+    fn part2(sequences: &Vec<Vec<i64>>, _cx: &Context) -> Result<Answer, SolveError> {
+        part2(sequences).map(Into::into).ok_or(SolveError::Overflow)
     }
 }
 
 
-fn part1(sequences: &[Vec<i64>]) -> BigInt {
+/// `None` if the differences get too big for an `i64`
+fn part1(sequences: &[Vec<i64>]) -> Option<BigInt> {
     sequences.iter()
         .map(|seq| {
             let mut current_seq = seq;
             let mut degrees = vec![];
             while current_seq.iter().any(|&x| x != 0) {
-                let degree = differentiate(current_seq);
+                let degree = differentiate(current_seq)?;
                 degrees.push(degree);
                 current_seq = &degrees[degrees.len()-1].1;
             }
@@ -32,51 +48,51 @@ fn part1(sequences: &[Vec<i64>]) -> BigInt {
             let mut zeros = current_seq.clone();
             zeros.push(0);
             while let Some((constant, _)) = degrees.pop() {
-                zeros = integrate(constant, &zeros);
+                zeros = integrate(constant, &zeros)?;
             }
 
-            Into::<BigInt>::into(zeros.pop().unwrap())
+            Some(Into::<BigInt>::into(zeros.pop().unwrap()))
         })
         .sum()
 }
 
-fn differentiate<I: Integer + Copy>(seq: &[I]) -> (I, Vec<I>) {
+fn differentiate<I: Integer + CheckedSub + Copy>(seq: &[I]) -> Option<(I, Vec<I>)> {
     let constant = seq[0];
-    let new = seq.iter()
-        .skip(1)
-        .scan(constant, |acc, y| {
-            let diff = *y - *acc;
-            *acc = *y;
-            Some(diff)
-        })
-        .collect();
-    (constant, new)
+    let new = seq.windows(2)
+        .map(|pair| pair[1].checked_sub(&pair[0]))
+        .collect::<Option<_>>()?;
+    Some((constant, new))
 }
 
-fn integrate(constant: i64, seq: &[i64]) -> Vec<i64> {
+fn integrate<I: Integer + CheckedAdd + Copy>(constant: I, seq: &[I]) -> Option<Vec<I>> {
     let mut new = Vec::with_capacity(seq.len()+1);
     new.push(constant);
-    new.extend(seq.iter().scan(constant, |acc, x| { *acc += x; Some(*acc) }));
-    new
+    let mut acc = constant;
+    for x in seq {
+        acc = acc.checked_add(x)?;
+        new.push(acc);
+    }
+    Some(new)
 }
 
-fn part2(sequences: &[Vec<i64>]) -> BigInt {
+/// `None` if the differences get too big for an `i64`
+fn part2(sequences: &[Vec<i64>]) -> Option<BigInt> {
     sequences.iter()
         .map(|seq| {
             let mut current_seq = seq;
             let mut degrees = vec![];
             while current_seq.iter().any(|&x| x != 0) {
-                let degree = differentiate(current_seq);
+                let degree = differentiate(current_seq)?;
                 degrees.push(degree);
                 current_seq = &degrees[degrees.len()-1].1;
             }
 
             let mut ans = 0;
             while let Some((constant, _)) = degrees.pop() {
-                ans = constant - ans;
+                ans = constant.checked_sub(ans)?;
             }
 
-            Into::<BigInt>::into(ans)
+            Some(Into::<BigInt>::into(ans))
         })
         .sum()
 }
@@ -89,7 +105,7 @@ mod tests {
     fn test_differentiate() {
         let orig = vec![1, 2, 3, 4, 5, 6, 7, 8];
         let test_deriv = vec![1, 1, 1, 1, 1, 1, 1];
-        let (constant, deriv) = differentiate(&orig);
+        let (constant, deriv) = differentiate(&orig).unwrap();
         assert_eq!(constant, 1);
         assert_eq!(test_deriv, deriv);
     }
@@ -99,7 +115,14 @@ mod tests {
         let orig = vec![1, 2, 3, 4, 5, 6, 7, 8];
         let test_deriv = vec![1, 1, 1, 1, 1, 1, 1];
         let constant = 1;
-        let integral = integrate(constant, &test_deriv);
+        let integral = integrate(constant, &test_deriv).unwrap();
         assert_eq!(orig, integral);
     }
+
+    #[test]
+    fn test_overflow() {
+        let sequences = Day09::parse(&"9223372036854775807 -9223372036854775808".into()).unwrap();
+        assert_eq!(Day09::part1(&sequences, &Context::new()), Err(SolveError::Overflow));
+        assert_eq!(Day09::part2(&sequences, &Context::new()), Err(SolveError::Overflow));
+    }
 }
@@ -1,10 +1,16 @@
 // use std::{collections::HashSet, hash::Hash};
 
-use crate::Day;
+use crate::{Day, Input, SolveError};
 
 pub struct Day10;
 
-impl Day for Day10 {}
+impl Day for Day10 {
+    type Parsed = ();
+
+    fn parse(_input: &Input) -> Result<(), SolveError> {
+        Ok(())
+    }
+}
 
 // type Point = (usize, usize);
 // 
@@ -1,84 +1,69 @@
-use crate::Day;
+use crate::{
+    grid::{Grid, Pos},
+    Answer, Context, Day, Example, Input, SolveError,
+};
 
 pub struct Day11;
 
 impl Day for Day11 {
-    async fn part1(input: String) -> String {
-        part1(input.trim()).to_string()
-    }
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....
+",
+        part1: Some("374"),
+        part2: Some("82000210"),
+    }];
+
+    /// Where the galaxies are
+    type Parsed = Grid<bool>;
 
-    async fn part2(input: String) -> String {
-        part2(input.trim()).to_string()
+    fn parse(input: &Input) -> Result<Grid<bool>, SolveError> {
+        Grid::parse(input, "`.` or `#`", |b| match b {
+            b'.' => Some(false),
+            b'#' => Some(true),
+            _ => None,
+        })
     }
-}
 
-use std::collections::HashSet;
+    fn part1(image: &Grid<bool>, _cx: &Context) -> Result<Answer, SolveError> {
+        Ok(solve(image, 2).into())
+    }
 
-fn part1(input: &str) -> usize {
-    solve::<1>(input)
+    fn part2(image: &Grid<bool>, _cx: &Context) -> Result<Answer, SolveError> {
+        Ok(solve(image, 1_000_000).into())
+    }
 }
 
-fn part2(input: &str) -> usize {
-    solve::<999999>(input)
-}
+/// Sums the distances between every pair of galaxies, once every row and column without any has grown `expansion` times as big
+fn solve(image: &Grid<bool>, expansion: usize) -> usize {
+    let rows = expanded((0..image.rows()).map(|row| image.row(row).contains(&true)), expansion);
+    let cols = expanded((0..image.cols()).map(|col| image.column(col).any(|&galaxy| galaxy)), expansion);
+    let galaxies: Vec<Pos> = image.iter()
+        .filter(|(_, &galaxy)| galaxy)
+        .map(|(pos, _)| Pos::new(rows[pos.row], cols[pos.col]))
+        .collect();
 
-fn solve<const C: usize>(input: &str) -> usize {
-    let mut n_rows = 0;
-    let mut n_cols = 0;
-    let galaxies: HashSet<_> = input.lines()
+    galaxies.iter()
         .enumerate()
-        .flat_map(|(row, line)| {
-            n_rows += 1;
-            n_cols = std::cmp::max(n_cols, line.trim().len());
-            line.trim()
-                .chars()
-                .enumerate()
-                .filter_map(move |(col, c)| (c == '#').then_some((row, col)))
-        })
-        .collect();
-    let galaxies = expand::<C>(galaxies, n_rows, n_cols);
-    let pairs = pairs(galaxies);
-    pairs.into_iter()
-        .map(|(gx1, gx2)| gx1.0.abs_diff(gx2.0) + gx1.1.abs_diff(gx2.1))
+        .flat_map(|(ind, gx1)| galaxies[ind + 1..].iter().map(|gx2| gx1.manhattan(*gx2)))
         .sum()
 }
 
-fn expand<const C: usize>(mut set: HashSet<(usize, usize)>, n_rows: usize, n_cols: usize) -> HashSet<(usize, usize)> {
-    let rows: Vec<_> = set.iter().map(|(row, _col)| row).copied().collect();
-    let cols: Vec<_> = set.iter().map(|(_row, col)| col).copied().collect();
-
-    // expand rows
-    (0..n_rows).rev().filter(|n| !rows.contains(n)).for_each(|n| {
-        set = set.iter().copied().map(|(row, col)| if row > n {
-            (row + C, col)
-        } else {
-            (row, col)
-        }).collect();
-    });
-
-    // expand columns
-    (0..n_cols).rev().filter(|n| !cols.contains(n)).for_each(|n| {
-        set = set.iter().copied().map(|(row, col)| if col > n {
-            (row, col + C)
-        } else {
-            (row, col)
-        }).collect();
-    });
-
-    set
-}
-
-fn pairs(single: HashSet<(usize, usize)>) -> HashSet<((usize, usize), (usize, usize))> {
-    let mut other = single.clone();
-    single.into_iter()
-        .flat_map({
-            move |gx1| {
-                other.remove(&gx1);
-                other.iter()
-                    .copied()
-                    .map(move |gx2| (std::cmp::min(gx1, gx2), std::cmp::max(gx1, gx2)))
-                    .collect::<Vec<_>>()
-            }
+/// Where each row (or column) ends up after the empty ones grow
+fn expanded(has_galaxy: impl Iterator<Item = bool>, expansion: usize) -> Vec<usize> {
+    has_galaxy
+        .scan(0, |next, has_galaxy| {
+            let at = *next;
+            *next += if has_galaxy { 1 } else { expansion };
+            Some(at)
         })
         .collect()
 }
@@ -1,14 +1,28 @@
-use crate::Day;
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day15;
 
 impl Day for Day15 {
-    async fn part1(input: String) -> String {
-        part1(&input.replace(['\n', '\r'], "")).to_string()
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7
+",
+        part1: Some("1320"),
+        part2: Some("145"),
+    }];
+
+    /// The initialization sequence, with any line breaks in it taken out
+    type Parsed = String;
+
+    fn parse(input: &Input) -> Result<String, SolveError> {
+        Ok(input.replace('\n', ""))
+    }
+
+    fn part1(sequence: &String, _cx: &Context) -> Result<Answer, SolveError> {
+        Ok(part1(sequence).into())
     }
 
-    async fn part2(input: String) -> String {
-        part2(&input.replace(['\n', '\r'], "")).to_string()
+    fn part2(sequence: &String, _cx: &Context) -> Result<Answer, SolveError> {
+        Ok(part2(sequence)?.into())
     }
 }
 
@@ -24,40 +38,41 @@ fn part1(s: &str) -> usize {
     s.split(',').map(hash).sum()
 }
 
-fn part2(s: &str) -> usize {
+fn part2(s: &str) -> Result<usize, SolveError> {
     let instructions = s.split(',');
     let mut hashmap: Vec<Vec<(String, u8)>> = (0..256).map(|_| Vec::new()).collect();
     for ins in instructions {
-        if ins.contains('=') {
-            let key = &ins[..ins.len()-2];
-            let lens = ins[ins.len()-1..].parse().unwrap();
+        if let Some((key, lens)) = ins.split_once('=') {
+            let lens = parse_at(s, lens, "a focal length")?;
             let slots = &mut hashmap[hash(key)];
             if let Some(slot) = slots.iter().position(|(this_key, _)| this_key == key) {
                 slots[slot].1 = lens;
             } else {
                 hashmap[hash(key)].push((key.to_owned(), lens));
             }
-        } else {
-            let key = &ins[..ins.len()-1];
+        } else if let Some(key) = ins.strip_suffix('-') {
             let slots = &mut hashmap[hash(key)];
             if let Some(slot) = slots.iter().position(|(this_key, _)| this_key == key) {
                 slots.remove(slot);
             }
+        } else {
+            return Err(SolveError::at_end(ins, "`=` and a focal length, or `-`").within(s, ins));
         }
     }
-    hashmap.into_iter()
+    Ok(hashmap.into_iter()
         .enumerate()
         .flat_map(|(ind, lenses)| lenses
             .into_iter()
             .enumerate()
             .map(move |(slot, (_, lens))| (ind + 1) * (slot + 1) * lens as usize))
-        .sum()
+        .sum())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    const TEST: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
+    const TEST: &str = Day15::EXAMPLES[0].input.trim_ascii_end();
 
     #[test]
     fn test_part1() {
@@ -66,6 +81,6 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST), 145);
+        assert_eq!(part2(TEST), Ok(145));
     }
 }
@@ -1,34 +1,64 @@
-use crate::Day;
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day19;
 
 impl Day for Day19 {
-    async fn part1(input: String) -> String {
-        part1(&input).to_string()
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}
+
+{x=787,m=2655,a=1222,s=2876}
+{x=1679,m=44,a=2067,s=496}
+{x=2036,m=264,a=79,s=2244}
+{x=2461,m=1339,a=466,s=291}
+{x=2127,m=1623,a=2188,s=1013}
+",
+        part1: Some("19114"),
+        part2: Some("167409079868000"),
+    }];
+
+    type Parsed = System;
+
+    fn parse(input: &Input) -> Result<System, SolveError> {
+        let (workflows, parts) = sections(input)?;
+        let workflows = parse_workflows(input, workflows)?;
+        let parts = parts.lines()
+            .map(|line| parse_part(input, line))
+            .collect::<Result<_, _>>()?;
+        Ok(System { workflows, parts })
+    }
+
+    fn part1(system: &System, _cx: &Context) -> Result<Answer, SolveError> {
+        part1(&system.workflows, &system.parts).map(Into::into).ok_or(SolveError::Overflow)
     }
 
-    async fn part2(input: String) -> String {
-        part2(&input).to_string()
+    fn part2(system: &System, cx: &Context) -> Result<Answer, SolveError> {
+        Ok(part2(&system.workflows, cx)?.into())
     }
 }
 
+pub struct System {
+    workflows: HashMap<String, Workflow>,
+    parts: Vec<Part>,
+}
+
 use std::{
     cmp::Ordering,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
 };
 
-fn part1(input: &str) -> usize {
-    let (workflows, parts) = input.split_once("\n\n").unwrap();
-    let workflows: HashMap<String, Workflow> = workflows.lines()
-        .map(|line| {
-            let ind = line.find('{').unwrap();
-            let name = line[..ind].to_owned();
-            let workflow = line[ind+1..].into();
-            (name, workflow)
-        })
-        .collect();
-    let accepted: Vec<_> = parts.lines()
-        .map(Into::<Part>::into)
+/// `None` if the sum of the ratings doesn't fit in a `usize`
+fn part1(workflows: &HashMap<String, Workflow>, parts: &[Part]) -> Option<usize> {
+    let accepted: Vec<_> = parts.iter()
         .filter(|part| {
             let mut action = Action::SendTo("in".to_owned());
             loop {
@@ -36,7 +66,7 @@ fn part1(input: &str) -> usize {
                     Action::Accept => { break true; }
                     Action::Reject => { break false; }
                     Action::SendTo(current) => {
-                        let workflow = workflows.get(&current).unwrap();
+                        let workflow = &workflows[&current];
                         workflow.conditions.iter().find_map(|(cond, next)| {
                             part.meets_condition(cond).then_some(next.clone())
                         }).unwrap()
@@ -46,8 +76,132 @@ fn part1(input: &str) -> usize {
         })
         .collect();
     accepted.into_iter()
-        .map(|part| part.x + part.m + part.a + part.s)
-        .sum()
+        .try_fold(0usize, |sum, part| {
+            [part.x, part.m, part.a, part.s].into_iter().try_fold(sum, usize::checked_add)
+        })
+}
+
+fn sections(input: &Input) -> Result<(&str, &str), SolveError> {
+    match input.sections()[..] {
+        [workflows, parts] => Ok((workflows, parts)),
+        _ => Err(SolveError::at_end(input, "the workflows and the parts, with a blank line between them")),
+    }
+}
+
+/// Parses the workflows and checks that following them always ends in `A` or `R`
+fn parse_workflows(input: &str, section: &str) -> Result<HashMap<String, Workflow>, SolveError> {
+    let named = section.lines()
+        .map(|line| {
+            line.split_once('{')
+                .ok_or_else(|| SolveError::at_end(line, "`{` after the workflow name").within(input, line))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let names: HashSet<&str> = named.iter().map(|(name, _)| *name).collect();
+    if !names.contains("in") {
+        return Err(SolveError::at(input, section, "a workflow named `in`"));
+    }
+
+    let mut workflows = HashMap::new();
+    let mut targets = HashMap::new();
+    for (name, body) in named {
+        let (workflow, sends_to) = parse_workflow(input, body, &names)?;
+        workflows.insert(name.to_owned(), workflow);
+        targets.insert(name, sends_to);
+    }
+
+    let mut done = HashSet::new();
+    for name in targets.keys() {
+        if let Some(target) = find_loop(name, &targets, &mut Vec::new(), &mut done) {
+            return Err(SolveError::at(input, target, "a workflow that does not lead back to itself"));
+        }
+    }
+    Ok(workflows)
+}
+
+/// Parses everything after the `{` of a workflow, also returning the names of the workflows it sends to
+fn parse_workflow<'a>(input: &str, s: &'a str, names: &HashSet<&str>) -> Result<(Workflow, Vec<&'a str>), SolveError> {
+    let rules = s
+        .strip_suffix('}')
+        .ok_or_else(|| SolveError::at_end(s, "`}` closing the workflow").within(input, s))?;
+    let mut sends_to = Vec::new();
+    let conditions = rules
+        .split(',')
+        .map(|rule| {
+            let (condition, target) = match rule.split_once(':') {
+                Some((left, right)) => {
+                    let (cat, num, ordering) = if let Some((cat, num)) = left.split_once('<') {
+                        (cat, num, Ordering::Less)
+                    } else if let Some((cat, num)) = left.split_once('>') {
+                        (cat, num, Ordering::Greater)
+                    } else {
+                        return Err(SolveError::at(input, left, "a condition like `x<10` or `x>10`"));
+                    };
+                    let cat = Category::try_from(cat)
+                        .map_err(|_| SolveError::at(input, cat, "`x`, `m`, `a` or `s`"))?;
+                    let num = parse_at(input, num, "a rating")?;
+                    let condition = match ordering {
+                        Ordering::Less => Condition::LessThan(cat, num),
+                        _ => Condition::GreaterThan(cat, num),
+                    };
+                    (condition, right)
+                }
+                None => (Condition::None, rule),
+            };
+            let action = Action::from(target);
+            if let Action::SendTo(_) = action {
+                if !names.contains(target) {
+                    return Err(SolveError::at(input, target, "`A`, `R` or the name of a workflow"));
+                }
+                sends_to.push(target);
+            }
+            Ok((condition, action))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if !matches!(conditions.last(), Some((Condition::None, _))) {
+        return Err(SolveError::at(input, &rules[rules.len()..], "a last rule without a condition"));
+    }
+    Ok((Workflow { conditions }, sends_to))
+}
+
+/// Depth-first search for a workflow that can be reached from itself, which would make solving never end
+fn find_loop<'a>(name: &'a str, targets: &HashMap<&'a str, Vec<&'a str>>, path: &mut Vec<&'a str>, done: &mut HashSet<&'a str>) -> Option<&'a str> {
+    if done.contains(name) {
+        return None;
+    }
+    path.push(name);
+    for &next in targets[name].iter() {
+        if path.contains(&next) {
+            return Some(next);
+        }
+        if let Some(found) = find_loop(next, targets, path, done) {
+            return Some(found);
+        }
+    }
+    path.pop();
+    done.insert(name);
+    None
+}
+
+fn parse_part(input: &str, line: &str) -> Result<Part, SolveError> {
+    let mut part = Part::default();
+    let attrs = line.strip_prefix('{')
+        .and_then(|attrs| attrs.strip_suffix('}'))
+        .ok_or_else(|| SolveError::at(input, line, "a part like `{x=1,m=2,a=3,s=4}`"))?;
+    for attr in attrs.split(',') {
+        let (cat, val) = attr
+            .split_once('=')
+            .ok_or_else(|| SolveError::at(input, attr, "a rating like `x=1`"))?;
+        let rating = match cat {
+            "x" => &mut part.x,
+            "m" => &mut part.m,
+            "a" => &mut part.a,
+            "s" => &mut part.s,
+            _ => { return Err(SolveError::at(input, cat, "`x`, `m`, `a` or `s`")); }
+        };
+        *rating = parse_at(input, val, "a rating")?;
+    }
+    Ok(part)
 }
 
 struct Workflow {
@@ -71,30 +225,6 @@ impl From<&str> for Action {
     }
 }
 
-impl From<&str> for Workflow {
-    fn from(s: &str) -> Self {
-        let conditions = s
-            .strip_suffix('}')
-            .unwrap()
-            .split(',').map(|cond_str| {
-                if let Some((left, right)) = cond_str.split_once(':') {
-                    if let Some((cat, num)) = left.split_once('<') {
-                        (Condition::LessThan(cat.into(), num.parse().unwrap()), right.into())
-                    } else if let Some((cat, num)) = left.split_once('>') {
-                        (Condition::GreaterThan(cat.into(), num.parse().unwrap()), right.into())
-                    } else {
-                        unreachable!()
-                    }
-                } else {
-                    (Condition::None, cond_str.into())
-                }
-
-            })
-            .collect();
-        Self { conditions }
-    }
-}
-
 enum Category {
     ExtremelyCool,
     Musical,
@@ -102,14 +232,16 @@ enum Category {
     Shiny,
 }
 
-impl From<&str> for Category {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for Category {
+    type Error = ();
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s {
-            "x" => Self::ExtremelyCool,
-            "m" => Self::Musical,
-            "a" => Self::Aerodynamic,
-            "s" => Self::Shiny,
-            _ => unreachable!(),
+            "x" => Ok(Self::ExtremelyCool),
+            "m" => Ok(Self::Musical),
+            "a" => Ok(Self::Aerodynamic),
+            "s" => Ok(Self::Shiny),
+            _ => Err(()),
         }
     }
 }
@@ -128,27 +260,6 @@ struct Part {
     s: usize,
 }
 
-impl From<&str> for Part {
-    fn from(s: &str) -> Self {
-        let mut part = Part::default();
-        for attr in s.strip_prefix('{')
-            .unwrap()
-            .strip_suffix('}')
-            .unwrap()
-            .split(',')
-        {
-            match attr.split_once('=').unwrap() {
-                ("x", val) => { part.x = val.parse().unwrap() }
-                ("m", val) => { part.m = val.parse().unwrap() }
-                ("a", val) => { part.a = val.parse().unwrap() }
-                ("s", val) => { part.s = val.parse().unwrap() }
-                _ => unreachable!()
-            }
-        }
-        part
-    }
-}
-
 impl Part {
     fn meets_condition(&self, cond: &Condition) -> bool {
         let (target, cat, val) = match cond {
@@ -166,59 +277,55 @@ impl Part {
     }
 }
 
-fn part2(input: &str) -> usize {
-    let (workflows, _parts) = input.split_once("\n\n").unwrap();
-    let workflows: HashMap<String, Workflow> = workflows.lines()
-        .map(|line| {
-            let ind = line.find('{').unwrap();
-            let name = line[..ind].to_owned();
-            let workflow = line[ind+1..].into();
-            (name, workflow)
-        })
-        .collect();
-
+fn part2(workflows: &HashMap<String, Workflow>, cx: &Context) -> Result<usize, SolveError> {
     let set = Set { x: 1..4001, m: 1..4001, a: 1..4001, s: 1..4001 };
-    count_accepted(set, workflows.get("in").unwrap().conditions.as_slice(), &workflows)
+    count_accepted(set, workflows["in"].conditions.as_slice(), workflows, cx)
 }
 
-fn count_accepted(set: Set, conditions: &[(Condition, Action)], workflows: &HashMap<String, Workflow>) -> usize {
-    match conditions.split_first() {
+fn count_accepted(
+    set: Set,
+    conditions: &[(Condition, Action)],
+    workflows: &HashMap<String, Workflow>,
+    cx: &Context,
+) -> Result<usize, SolveError> {
+    cx.check()?;
+    Ok(match conditions.split_first() {
         Some(((cond, action), rest)) => match cond {
             Condition::None => match action {
                 Action::Accept => set.size(),
                 Action::Reject => 0,
                 Action::SendTo(next) => {
-                    let next_conditions = workflows.get(next).unwrap().conditions.as_slice();
-                    count_accepted(set, next_conditions, workflows)
+                    let next_conditions = workflows[next].conditions.as_slice();
+                    count_accepted(set, next_conditions, workflows, cx)?
                 },
             }
             Condition::LessThan(cat, split) => {
                 let (left, right) = set.split(cat, *split);
                 match action {
-                    Action::Accept => left.size() + count_accepted(right, rest, workflows),
-                    Action::Reject => count_accepted(right, rest, workflows),
+                    Action::Accept => left.size() + count_accepted(right, rest, workflows, cx)?,
+                    Action::Reject => count_accepted(right, rest, workflows, cx)?,
                     Action::SendTo(next) => {
-                        let left_workflows = workflows.get(next).unwrap().conditions.as_slice();
-                        count_accepted(left, left_workflows, workflows)
-                            + count_accepted(right, rest, workflows)
+                        let left_workflows = workflows[next].conditions.as_slice();
+                        count_accepted(left, left_workflows, workflows, cx)?
+                            + count_accepted(right, rest, workflows, cx)?
                     }
                 }
             }
             Condition::GreaterThan(cat, split) => {
-                let (left, right) = set.split(cat, *split+1);
+                let (left, right) = set.split(cat, split.saturating_add(1));
                 match action {
-                    Action::Accept => count_accepted(left, rest, workflows) + right.size(),
-                    Action::Reject => count_accepted(left, rest, workflows),
+                    Action::Accept => count_accepted(left, rest, workflows, cx)? + right.size(),
+                    Action::Reject => count_accepted(left, rest, workflows, cx)?,
                     Action::SendTo(next) => {
-                        let right_workflows = workflows.get(next).unwrap().conditions.as_slice();
-                        count_accepted(left, rest, workflows)
-                            + count_accepted(right, right_workflows, workflows)
+                        let right_workflows = workflows[next].conditions.as_slice();
+                        count_accepted(left, rest, workflows, cx)?
+                            + count_accepted(right, right_workflows, workflows, cx)?
                     }
                 }
             }
         }
         None => 0,
-    }
+    })
 }
 
 use std::ops::Range;
@@ -231,22 +338,23 @@ struct Set {
 }
 
 impl Set {
+    /// Splits into the ratings below `split` and the rest, either of which can be empty
     fn split(self, cat: &Category, split: usize) -> (Self, Self) {
         match cat {
             Category::ExtremelyCool => {
-                assert!((self.x.start <= split) && (split <= self.x.end));
+                let split = split.clamp(self.x.start, self.x.end);
                 (Set { x: self.x.start..split, ..self.clone() }, Set { x: split..self.x.end, ..self })
             }
             Category::Musical => {
-                assert!((self.m.start <= split) && (split <= self.m.end));
+                let split = split.clamp(self.m.start, self.m.end);
                 (Set { m: self.m.start..split, ..self.clone() }, Set { m: split..self.m.end, ..self })
             }
             Category::Aerodynamic => {
-                assert!((self.a.start <= split) && (split <= self.a.end));
+                let split = split.clamp(self.a.start, self.a.end);
                 (Set { a: self.a.start..split, ..self.clone() }, Set { a: split..self.a.end, ..self })
             }
             Category::Shiny => {
-                assert!((self.s.start <= split) && (split <= self.s.end));
+                let split = split.clamp(self.s.start, self.s.end);
                 (Set { s: self.s.start..split, ..self.clone() }, Set { s: split..self.s.end, ..self })
             }
         }
@@ -256,3 +364,27 @@ impl Set {
         self.x.len() * self.m.len() * self.a.len() * self.s.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions_outside_the_set() {
+        let part2 = |workflows: &str| {
+            let system = Day19::parse(&Input::from(format!("{workflows}\n\n{{x=1,m=2,a=3,s=4}}"))).unwrap();
+            Day19::part2(&system, &Context::new())
+        };
+        let all = Answer::from(4000usize.pow(4));
+        assert_eq!(part2("in{x<5000:A,R}"), Ok(all.clone()));
+        assert_eq!(part2("in{x>4001:R,A}"), Ok(all));
+        assert_eq!(part2("in{x<0:A,R}"), Ok(Answer::from(0usize)));
+        assert_eq!(part2("in{x>100:b,R}\nb{x<50:A,x<3000:A,R}"), Ok(Answer::from(2899 * 4000usize.pow(3))));
+    }
+
+    #[test]
+    fn test_rating_sum_overflow() {
+        let system = Day19::parse(&Input::from(format!("in{{A}}\n\n{{x={},m=1,a=0,s=0}}", usize::MAX))).unwrap();
+        assert_eq!(Day19::part1(&system, &Context::new()), Err(SolveError::Overflow));
+    }
+}
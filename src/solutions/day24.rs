@@ -1,39 +1,64 @@
-use crate::Day;
+use crate::{parse_at, Answer, Context, Day, Example, Input, SolveError};
 
 pub struct Day24;
 
 impl Day for Day24 {
-    async fn part1(input: String) -> String {
-        let stones: Vec<_> = input
-            .replace("  ", " ")
-            .trim()
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: "19, 13, 30 @ -2,  1, -2
+18, 19, 22 @ -1, -1, -2
+20, 25, 34 @ -2, -2, -4
+12, 31, 28 @ -1, -2, -1
+20, 19, 15 @  1, -5, -3
+",
+        part1: None,
+        part2: None,
+    }];
+
+    type Parsed = Vec<Stone>;
+
+    fn parse(input: &Input) -> Result<Vec<Stone>, SolveError> {
+        input
             .lines()
-            .map(parse_line)
-            .collect();
+            .into_iter()
+            .map(|line| parse_line(line).map_err(|err| err.within(input, line)))
+            .collect()
+    }
 
-        let mut n = 0;
-        for i in 0..stones.len() - 1 {
+    fn part1(stones: &Vec<Stone>, cx: &Context) -> Result<Answer, SolveError> {
+
+        let mut n: usize = 0;
+        for i in 0..stones.len() {
+            cx.check()?;
             for j in i + 1..stones.len() {
                 if let Some(intersection) = find_intersection_2d(stones[i], stones[j]) {
                     let (x, y, tu, tv) = intersection;
-                    let in_bounds = 200000000000000.0 <= x && x <= 400000000000000.0 && 200000000000000.0 <= y && y <= 400000000000000.0;
+                    let area = 200000000000000.0..=400000000000000.0;
+                    let in_bounds = area.contains(&x) && area.contains(&y);
                     let in_future = tu > 0.0 && tv > 0.0;
                     if in_bounds && in_future { n += 1; }
                 }
             }
         }
 
-        n.to_string()
+        Ok(n.into())
     }
 }
 
 type Stone = ([i64; 3], [i64; 3]);
 
-fn parse_line(line: &str) -> Stone {
-    let (left, right) = line.split_once(" @ ").unwrap();
-    let p: [i64; 3] = left.split(", ").map(|x| x.parse().unwrap()).collect::<Vec<_>>().try_into().unwrap();
-    let d: [i64; 3] = right.split(", ").map(|x| x.parse().unwrap()).collect::<Vec<_>>().try_into().unwrap();
-    (p, d)
+fn parse_line(line: &str) -> Result<Stone, SolveError> {
+    let (left, right) = line
+        .split_once(" @ ")
+        .ok_or_else(|| SolveError::at_end(line, "` @ ` between the position and the velocity"))?;
+    Ok((parse_vector(line, left)?, parse_vector(line, right)?))
+}
+
+fn parse_vector(line: &str, s: &str) -> Result<[i64; 3], SolveError> {
+    s.split(',')
+        .map(|x| parse_at(line, x.trim(), "a number"))
+        .collect::<Result<Vec<_>, _>>()?
+        .try_into()
+        .map_err(|_| SolveError::at(line, s, "three numbers separated by `, `"))
 }
 
 fn find_intersection_2d(stone1: Stone, stone2: Stone) -> Option<(f64, f64, f64, f64)> {
@@ -57,30 +82,22 @@ fn find_intersection_2d(stone1: Stone, stone2: Stone) -> Option<(f64, f64, f64,
 mod tests {
     use super::*;
 
-    const TEST: &str = r#"
-19, 13, 30 @ -2,  1, -2
-18, 19, 22 @ -1, -1, -2
-20, 25, 34 @ -2, -2, -4
-12, 31, 28 @ -1, -2, -1
-20, 19, 15 @  1, -5, -3
-"#;
+    const TEST: &str = Day24::EXAMPLES[0].input;
 
     #[test]
     fn test_part1() {
         let stones: Vec<_> = TEST
-            .replace("  ", " ")
-            .trim()
             .lines()
-            .map(parse_line)
+            .map(|line| parse_line(line).unwrap())
             .collect();
 
-        let mut n = 0;
+        let mut n: usize = 0;
         for i in 0..stones.len() - 1 {
             for j in i + 1..stones.len() {
                 if let Some(intersection) = find_intersection_2d(stones[i], stones[j]) {
-                    println!("{:?}, {:?} => {:?}", stones[i], stones[j], intersection);
                     let (x, y, tu, tv) = intersection;
-                    let in_bounds = 7.0 <= x && x <= 27.0 && 7.0 <= y && y <= 27.0;
+                    let area = 7.0..=27.0;
+                    let in_bounds = area.contains(&x) && area.contains(&y);
                     let in_future = tu > 0.0 && tv > 0.0;
                     if in_bounds && in_future { n += 1; }
                 }
@@ -1,3 +1,7 @@
+use std::{any::Any, sync::Arc};
+
+use crate::{Answer, Context, Day, Example, Input, SolveError};
+
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -23,3 +27,178 @@ pub mod day22;
 pub mod day23;
 pub mod day24;
 pub mod day25;
+
+/// A day's `Day::Parsed` with the type erased, cheap to clone so both parts (or a cache) can share it
+#[derive(Clone)]
+pub struct ParsedInput(Arc<dyn Any + Send + Sync>);
+
+impl ParsedInput {
+    fn get<D: Day>(&self) -> &D::Parsed {
+        self.0
+            .downcast_ref()
+            .unwrap_or_else(|| panic!("input parsed by another day was handed to {}", std::any::type_name::<D>()))
+    }
+}
+
+/// A day's `Day::parse` with the type erased, so every day fits in the same table
+pub type Parser = fn(&Input) -> Result<ParsedInput, SolveError>;
+
+/// One part of a day with the type erased. Handing it another day's `ParsedInput` is a bug, and panics.
+pub type Solver = fn(&ParsedInput, &Context) -> Result<Answer, SolveError>;
+
+/// Everything the server (or anything else) needs to know about a day
+pub struct DayEntry {
+    pub number: u8,
+    /// Name of the type implementing `Day`
+    pub name: &'static str,
+    /// The puzzle's title on adventofcode.com
+    pub title: &'static str,
+    /// The parts that have actually been solved
+    pub parts: &'static [u8],
+    pub examples: &'static [Example],
+    /// The day's module, as it was compiled
+    pub source: &'static str,
+    pub parse: Parser,
+    pub part1: Solver,
+    pub part2: Solver,
+}
+
+impl DayEntry {
+    pub fn has_part(&self, part: u8) -> bool {
+        self.parts.contains(&part)
+    }
+
+    pub fn solver(&self, part: u8) -> Option<Solver> {
+        match part {
+            1 => Some(self.part1),
+            2 => Some(self.part2),
+            _ => None,
+        }
+    }
+
+    /// Parses and solves one part, for when there's no reason to keep the parsed input
+    pub fn solve(&self, part: u8, input: &Input, cx: &Context) -> Result<Answer, SolveError> {
+        let solver = self.solver(part).ok_or(SolveError::Unimplemented { part })?;
+        solver(&(self.parse)(input)?, cx)
+    }
+}
+
+fn parse<D: Day>(input: &Input) -> Result<ParsedInput, SolveError> {
+    Ok(ParsedInput(Arc::new(D::parse(input)?)))
+}
+
+fn part1<D: Day>(parsed: &ParsedInput, cx: &Context) -> Result<Answer, SolveError> {
+    D::part1(parsed.get::<D>(), cx)
+}
+
+fn part2<D: Day>(parsed: &ParsedInput, cx: &Context) -> Result<Answer, SolveError> {
+    D::part2(parsed.get::<D>(), cx)
+}
+
+/// Looks up a day by its number
+pub fn day(number: u8) -> Option<&'static DayEntry> {
+    DAYS.iter().find(|entry| entry.number == number)
+}
+
+macro_rules! days {
+    ($($number:literal => $module:ident::$day:ident $title:literal [$($part:literal),*]),* $(,)?) => {
+        /// Every day, in order
+        pub static DAYS: &[DayEntry] = &[$(
+            DayEntry {
+                number: $number,
+                name: stringify!($day),
+                title: $title,
+                parts: &[$($part),*],
+                examples: <$module::$day as Day>::EXAMPLES,
+                source: include_str!(concat!(stringify!($module), ".rs")),
+                parse: parse::<$module::$day>,
+                part1: part1::<$module::$day>,
+                part2: part2::<$module::$day>,
+            },
+        )*];
+    };
+}
+
+days! {
+    1 => day01::Day01 "Trebuchet?!" [1, 2],
+    2 => day02::Day02 "Cube Conundrum" [1, 2],
+    3 => day03::Day03 "Gear Ratios" [1, 2],
+    4 => day04::Day04 "Scratchcards" [1, 2],
+    5 => day05::Day05 "If You Give A Seed A Fertilizer" [1, 2],
+    6 => day06::Day06 "Wait For It" [1, 2],
+    7 => day07::Day07 "Camel Cards" [1],
+    8 => day08::Day08 "Haunted Wasteland" [1, 2],
+    9 => day09::Day09 "Mirage Maintenance" [1, 2],
+    10 => day10::Day10 "Pipe Maze" [],
+    11 => day11::Day11 "Cosmic Expansion" [1, 2],
+    12 => day12::Day12 "Hot Springs" [],
+    13 => day13::Day13 "Point of Incidence" [],
+    14 => day14::Day14 "Parabolic Reflector Dish" [],
+    15 => day15::Day15 "Lens Library" [1, 2],
+    16 => day16::Day16 "The Floor Will Be Lava" [1, 2],
+    17 => day17::Day17 "Clumsy Crucible" [],
+    18 => day18::Day18 "Lavaduct Lagoon" [],
+    19 => day19::Day19 "Aplenty" [1, 2],
+    20 => day20::Day20 "Pulse Propagation" [],
+    21 => day21::Day21 "Step Counter" [],
+    22 => day22::Day22 "Sand Slabs" [],
+    23 => day23::Day23 "A Long Walk" [],
+    24 => day24::Day24 "Never Tell Me The Odds" [1],
+    25 => day25::Day25 "Snowverload" [2],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_in_order() {
+        let numbers: Vec<u8> = DAYS.iter().map(|entry| entry.number).collect();
+        assert_eq!(numbers, (1..=25).collect::<Vec<u8>>());
+        for entry in DAYS {
+            assert_eq!(entry.name, format!("Day{:02}", entry.number));
+        }
+    }
+
+    #[test]
+    fn test_parts_match_implementations() {
+        for entry in DAYS {
+            // Days that are done have to parse something first, so use their example
+            let input = Input::new(entry.examples.first().map_or("", |example| example.input));
+            let parsed = (entry.parse)(&input)
+                .unwrap_or_else(|error| panic!("day {} can't parse its example: {error}", entry.number));
+            for part in 1..=2 {
+                let result = (entry.solver(part).unwrap())(&parsed, &Context::new());
+                let unimplemented = result == Err(SolveError::Unimplemented { part });
+                assert_eq!(
+                    entry.has_part(part),
+                    !unimplemented,
+                    "day {} part {part} is listed wrong in DAYS",
+                    entry.number
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_examples() {
+        for entry in DAYS {
+            for (index, example) in entry.examples.iter().enumerate() {
+                for (part, expected) in [(1, example.part1), (2, example.part2)] {
+                    let Some(expected) = expected else { continue };
+                    // Pasted into a browser, the examples would come with CRLF line endings
+                    for input in [example.input.to_string(), example.input.replace('\n', "\r\n")] {
+                        let result = entry.solve(part, &Input::from(input), &Context::new());
+                        assert_eq!(
+                            result.map(|answer| answer.to_string()).as_deref(),
+                            Ok(expected),
+                            "day {} part {part} on example {}",
+                            entry.number,
+                            index + 1
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
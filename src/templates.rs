@@ -0,0 +1,132 @@
+//! The HTML templates under `assets/`, named by their path there, like `templates/index.html`.
+//! Release builds have them built in. Debug builds read them from disk and read them again once one changes,
+//! so seeing an edit only takes reloading the page.
+
+use std::sync::{LazyLock, RwLock};
+
+use minijinja::{Environment, Value};
+
+/// Every template, built in for release builds and watched in debug builds
+const SOURCES: &[(&str, &str)] = &[
+    ("layouts/root.html", include_str!("../assets/layouts/root.html")),
+    ("layouts/app.html", include_str!("../assets/layouts/app.html")),
+    ("templates/index.html", include_str!("../assets/templates/index.html")),
+    ("templates/solutions.html", include_str!("../assets/templates/solutions.html")),
+    ("templates/answer.html", include_str!("../assets/templates/answer.html")),
+    ("templates/error.html", include_str!("../assets/templates/error.html")),
+    ("templates/both.html", include_str!("../assets/templates/both.html")),
+    ("templates/job.html", include_str!("../assets/templates/job.html")),
+    ("templates/status.html", include_str!("../assets/templates/status.html")),
+    ("templates/source.html", include_str!("../assets/templates/source.html")),
+    ("templates/source_code.html", include_str!("../assets/templates/source_code.html")),
+    ("templates/result.html", include_str!("../assets/templates/result.html")),
+];
+
+static TEMPLATES: LazyLock<RwLock<Templates>> = LazyLock::new(|| RwLock::new(Templates::load()));
+
+struct Templates {
+    env: Environment<'static>,
+    /// The newest modification time of the files the templates were read from
+    #[cfg(debug_assertions)]
+    modified: Option<std::time::SystemTime>,
+}
+
+impl Templates {
+    fn load() -> Self {
+        let mut env = Environment::new();
+        for &(name, source) in SOURCES {
+            #[cfg(debug_assertions)]
+            let added = match std::fs::read_to_string(Self::path(name)) {
+                Ok(source) => env.add_template_owned(name, source),
+                Err(_) => env.add_template(name, source),
+            };
+            #[cfg(not(debug_assertions))]
+            let added = env.add_template(name, source);
+
+            // Shown in place of the template, rather than bringing the whole page down
+            if let Err(error) = added {
+                tracing::error!("template {name} is broken: {error:#}");
+                let _ = env.add_template_owned(name, format!("template {name} is broken: {error}"));
+            }
+        }
+        Self {
+            env,
+            #[cfg(debug_assertions)]
+            modified: Self::newest_modification(),
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn path(name: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(name)
+    }
+
+    #[cfg(debug_assertions)]
+    fn newest_modification() -> Option<std::time::SystemTime> {
+        SOURCES
+            .iter()
+            .filter_map(|(name, _)| std::fs::metadata(Self::path(name)).and_then(|metadata| metadata.modified()).ok())
+            .max()
+    }
+}
+
+/// Renders the template called `name`. Panics if there's no such template or rendering it fails,
+/// which the tests rule out for the pages and fragments the server renders.
+pub fn render(name: &str, ctx: Value) -> String {
+    #[cfg(debug_assertions)]
+    {
+        let modified = Templates::newest_modification();
+        if modified != TEMPLATES.read().unwrap().modified {
+            tracing::info!("reloading the templates");
+            *TEMPLATES.write().unwrap() = Templates::load();
+        }
+    }
+
+    TEMPLATES
+        .read()
+        .unwrap()
+        .env
+        .get_template(name)
+        .and_then(|template| template.render(ctx))
+        .unwrap_or_else(|error| panic!("could not render {name}: {error:#}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use minijinja::context;
+
+    use super::*;
+
+    #[test]
+    fn test_templates_render() {
+        let days = [context!(number => 1, title => "Trebuchet?!", parts => [1, 2])];
+        let page = render("templates/index.html", context!(days => days));
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("href=\"/day/1\""));
+
+        let page = render(
+            "templates/solutions.html",
+            context!(day => 1, title => "<Trebuchet>", parts => [1], examples => ["a\"b"]),
+        );
+        assert!(page.contains("<main>"));
+        assert!(page.contains("&lt;Trebuchet&gt;"));
+        assert!(page.contains("data-example=\"a&quot;b\""));
+
+        let page = render("templates/source.html", context!(day => 1, title => "Trebuchet?!", lines => ["a", "<b>"]));
+        assert!(page.contains("<span class=\"line\" id=\"L2\"><a class=\"line-number\" href=\"#L2\">2</a><b></span>"));
+
+        let page = render(
+            "templates/result.html",
+            context!(day => 1, title => "Trebuchet?!", part => 2, answer => "<42>", input => "00ab"),
+        );
+        assert!(page.contains("Part 2") && page.contains("&lt;42&gt;"));
+
+        let page = render("templates/status.html", context!(status => "404 Not Found", message => "Nothing."));
+        assert!(page.contains("<main>") && page.contains("404 Not Found"));
+
+        assert_eq!(render("templates/error.html", context!(message => "<x>")), "<span class=\"text-red-600\">Could not solve: &lt;x&gt;</span>");
+        for (name, _) in SOURCES {
+            assert!(TEMPLATES.read().unwrap().env.get_template(name).is_ok(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Logging: the subscriber, and the spans requests and solves are logged under
+
+use std::{sync::Arc, time::Duration};
+
+use axum::http::Request;
+use tracing::{field::Empty, Span};
+
+use aoc2023::{Answer, Input, SolveError};
+
+use crate::{
+    config::{Config, LogFormat},
+    metrics::Metrics,
+    results,
+};
+
+pub fn init(config: &Config) {
+    let subscriber = tracing_subscriber::fmt().with_max_level(config.log_level);
+    match config.log_format {
+        LogFormat::Human => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+/// The span each request is handled in
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    tracing::info_span!("request", method = %request.method(), uri = %request.uri())
+}
+
+/// One part being solved: the span its events are logged under, which gets how long it took and how it went
+/// once it's done, and the metrics that are counted then too
+#[derive(Clone)]
+pub struct Solve {
+    pub day: u8,
+    pub part: u8,
+    /// `results::fingerprint` of the input, for storing the answer under
+    pub input: u64,
+    pub span: Span,
+    metrics: Arc<Metrics>,
+}
+
+impl Solve {
+    pub fn new(metrics: &Arc<Metrics>, day: u8, part: u8, input: &Input) -> Self {
+        let span = tracing::info_span!(
+            "solve",
+            day,
+            part,
+            input_bytes = input.len(),
+            elapsed_ms = Empty,
+            outcome = Empty,
+        );
+        Self { day, part, input: results::fingerprint(input), span, metrics: metrics.clone() }
+    }
+
+    /// Records the result of a solve that ran for `elapsed`, or that never ran because the input didn't parse
+    pub fn finish(&self, elapsed: Option<Duration>, result: &Result<Answer, SolveError>) {
+        let outcome = match result {
+            Ok(_) => "answered",
+            Err(error) => error.kind(),
+        };
+        self.span.record("outcome", outcome);
+        match elapsed {
+            Some(elapsed) => {
+                self.span.record("elapsed_ms", elapsed.as_secs_f64() * 1000.0);
+                self.metrics.solved(self.day, self.part, elapsed, result);
+            }
+            None => {
+                if let Err(error) = result {
+                    self.metrics.failed(self.day, self.part, error);
+                }
+            }
+        }
+
+        match result {
+            Ok(_) => tracing::info!(parent: &self.span, "solved"),
+            Err(error @ (SolveError::Timeout { .. } | SolveError::Cancelled)) => {
+                tracing::warn!(parent: &self.span, "gave up: {error}")
+            }
+            // The panic itself was logged with its backtrace when it happened
+            Err(error @ SolveError::Panicked(_)) => tracing::error!(parent: &self.span, "{error}"),
+            Err(error) => tracing::info!(parent: &self.span, "no answer: {error}"),
+        }
+    }
+}